@@ -1,9 +1,103 @@
 use crate::cmd::Command;
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use console::style;
-use std::path::Path;
+use humansize::{DECIMAL, format_size};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::{Command as StdCommand, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+pub(crate) const TARGET_TRIPLE: &str = "riscv32imac-unknown-none-elf";
+
+// 默认的固件加载地址
+const DEFAULT_LOAD_ADDR: u32 = 0x3000_0000;
+
+// 标记上一次构建是否启用了 --panic-abort
+const PANIC_ABORT_MARKER: &str = ".panic-abort";
+
+// --stack-guard 生成的链接脚本片段及其中的符号
+const STACK_GUARD_SCRIPT: &str = "stack_guard.ld";
+const STACK_GUARD_START_SYMBOL: &str = "__ecos_stack_guard_start";
+const STACK_GUARD_END_SYMBOL: &str = "__ecos_stack_guard_end";
+
+// CARGO_ENCODED_RUSTFLAGS 的参数分隔符
+const RUSTFLAGS_SEPARATOR: &str = "\x1f";
+
+// 超过 ecos_flash_size_bytes 的该比例时提示使用 LTO
+const FLASH_SIZE_WARN_RATIO: f64 = 0.8;
+
+// --arch-flags 接受的 RISC-V target feature
+const RISCV_TARGET_FEATURES: [&str; 16] = [
+    "m", "a", "f", "d", "c", "e", "v", "zicsr", "zifencei", "zmmul", "zba", "zbb", "zbc", "zbs",
+    "zfh", "relax",
+];
+
+// 内置的 no_std 兼容 crate 列表
+const KNOWN_NOSTD_JSON: &str = include_str!("known_nostd.json");
+const KNOWN_NOSTD_URL: &str =
+    "https://raw.githubusercontent.com/ECOS-C1-SDK4Heke/cargo-ecos/main/src/cmd/known_nostd.json";
+
+// SDK 记录硬件资源占用（中断、DMA 通道等）的 ELF 段
+const RESOURCES_SECTION: &str = ".ecos_resources";
+
+// -Z emit-stack-sizes 生成的栈帧大小段
+const STACK_SIZES_SECTION: &str = ".stack_sizes";
+
+// build/ 下的大小历史记录
+pub(crate) const SIZE_HISTORY_FILE: &str = "size_history.csv";
+const SIZE_HISTORY_HEADER: &str = "timestamp,git_commit,profile,bin_size_bytes,hex_size_bytes";
+const SIZE_TREND_ROWS: usize = 10;
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum MapSort {
+    /// Largest input sections first
+    Size,
+    /// Alphabetically by section name
+    Name,
+    /// By load address
+    Addr,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum LtoMode {
+    Thin,
+    Fat,
+}
+
+impl LtoMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LtoMode::Thin => "thin",
+            LtoMode::Fat => "fat",
+        }
+    }
+}
+
+// --compact-report 时关闭所有进度输出
+static COMPACT_REPORT: AtomicBool = AtomicBool::new(false);
+
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if !COMPACT_REPORT.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// 子进程的输出方式，--compact-report 时丢弃
+fn child_stdio() -> Stdio {
+    if COMPACT_REPORT.load(Ordering::Relaxed) {
+        Stdio::null()
+    } else {
+        Stdio::inherit()
+    }
+}
 
 #[derive(Args)]
 pub struct BuildCommand {
@@ -19,6 +113,248 @@ pub struct BuildCommand {
     #[arg(short, long)]
     sections: bool,
 
+    /// Use the `abort` panic strategy (-C panic=abort) for minimal binary size
+    #[arg(long)]
+    panic_abort: bool,
+
+    /// Load address of the firmware image (hex, e.g. 0x30000000)
+    #[arg(long, value_name = "HEX_ADDRESS", value_parser = parse_hex_addr)]
+    addr: Option<u32>,
+
+    /// Generate a cargo build timing report (--timings=html)
+    #[arg(long)]
+    timings: bool,
+
+    /// Open the timing report in the default browser (implies --timings)
+    #[arg(long)]
+    timings_open: bool,
+
+    /// Build with the nightly toolchain (cargo +nightly build)
+    #[arg(long)]
+    nightly: bool,
+
+    /// Embed a firmware identifier via the ECOS_FIRMWARE_ID env var
+    /// (defaults to the short git commit hash)
+    #[arg(long, value_name = "STRING", num_args = 0..=1, default_missing_value = "")]
+    firmware_id: Option<String>,
+
+    /// Embed the short git commit hash via the ECOS_GIT_HASH env var and create
+    /// src/version.rs (if missing) exposing it as `GIT_HASH`
+    #[arg(long)]
+    embed_git_hash: bool,
+
+    /// Enable link-time optimisation for the selected profile
+    #[arg(long, value_enum, value_name = "MODE")]
+    lto: Option<LtoMode>,
+
+    /// Do not embed LLVM bitcode in .rlib files (-C embed-bitcode=no), which makes
+    /// them smaller. Incompatible with any LTO (--lto or lto in the Cargo.toml
+    /// profile), which needs the bitcode
+    #[arg(long)]
+    no_embed_bitcode: bool,
+
+    /// Reserve a stack guard region of SIZE bytes (e.g. 256, 0x100, 1K) after .bss,
+    /// sized by the ECOS_STACK_GUARD_SIZE linker symbol
+    #[arg(long, value_name = "SIZE", value_parser = crate::cmd::parse_size)]
+    stack_guard: Option<u64>,
+
+    /// Fail if the worst-case call stack depth exceeds BYTES (e.g. 4K). Needs nightly
+    /// (-Z emit-stack-sizes); C sources are covered through -fstack-usage .su files.
+    /// The deepest call chain is reported
+    #[arg(long, value_name = "BYTES", value_parser = crate::cmd::parse_size, conflicts_with = "no_link")]
+    max_stack_depth: Option<u64>,
+
+    /// After the firmware build, also run `cargo check` for a host target
+    /// (defaults to the host triple reported by `rustc -vV`)
+    #[arg(long, value_name = "HOST_TARGET", num_args = 0..=1, default_missing_value = "")]
+    cross_check: Option<String>,
+
+    /// Print only a single OK/FAIL summary line (for CI)
+    #[arg(long)]
+    compact_report: bool,
+
+    /// Disable ANSI colours in the build output (and cargo's) even on a TTY,
+    /// e.g. for serial consoles such as screen or minicom
+    #[arg(long)]
+    no_color_output: bool,
+
+    /// Treat rustc warnings as errors (-D warnings) and summarise them by lint
+    #[arg(long)]
+    fail_on_warning: bool,
+
+    /// Check Cargo.lock against the list of known no_std-compatible crates
+    #[arg(long)]
+    check_deps: bool,
+
+    /// Re-fetch the known no_std crate list (from ecos_known_nostd_url or the default URL)
+    #[arg(long)]
+    update_known_nostd: bool,
+
+    /// Also generate a Motorola S-record file (build/<project>.srec)
+    #[arg(long)]
+    srec: bool,
+
+    /// Extra objdump flags for the disassembly, e.g. "-S -M no-aliases --demangle"
+    /// (defaults to ecos_objdump_flags in Cargo.toml)
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    objdump_flags: Option<String>,
+
+    /// Skip the objdump disassembly (build/<project>.txt)
+    #[arg(long, conflicts_with = "objdump_flags")]
+    no_asm_output: bool,
+
+    /// Skip the Verilog hex file (build/<project>.hex)
+    #[arg(long)]
+    no_hex_output: bool,
+
+    /// Do not read the hardware resource usage from the ELF's .ecos_resources section
+    #[arg(long)]
+    skip_resources_section: bool,
+
+    /// Number of codegen units (-C codegen-units=N); fewer units give smaller,
+    /// better optimised code at the cost of build parallelism
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    codegen_units: Option<u32>,
+
+    /// Optimisation level (-C opt-level): 0 = none, 1-3 = speed (3 is the
+    /// release default), s = size, z = size more aggressively (often smallest)
+    #[arg(long, value_name = "LEVEL", value_parser = ["0", "1", "2", "3", "s", "z"])]
+    opt_level: Option<String>,
+
+    /// Type-check all crates and emit object files for the firmware crate, but skip the
+    /// final link step (runs `cargo rustc --profile check -- --emit=dep-info,metadata,obj`).
+    /// No ELF, .bin or .hex is generated, so post-build steps and reports are skipped
+    #[arg(long, conflicts_with_all = ["compact_report", "srec", "sections", "cross_check"])]
+    no_link: bool,
+
+    /// RISC-V target features passed as -C target-feature, as a comma separated list
+    /// of +/- prefixed extensions, e.g. "+m,+a,-c"
+    /// (defaults to ecos_target_features in Cargo.toml)
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true, value_parser = parse_arch_flags)]
+    arch_flags: Option<String>,
+
+    /// Alternative linker. `mold` is used through the gcc driver (-fuse-ld=mold),
+    /// `lld` through clang (--target=riscv32, -fuse-ld=lld); anything else is passed
+    /// as -C linker=<LINKER>
+    #[arg(long, value_name = "LINKER", conflicts_with = "no_link")]
+    linker: Option<String>,
+
+    /// Build once per Cargo feature (each enabled on its own, in parallel) and
+    /// compare the .bin sizes against a build without any features
+    #[arg(long, conflicts_with_all = ["compact_report", "no_link"])]
+    features_matrix: bool,
+
+    /// Write all ELF symbols (address, size, kind, demangled name) to build/symbols.json
+    #[arg(long)]
+    symbols_json: bool,
+
+    /// Only include symbols whose demangled name matches REGEX in build/symbols.json
+    #[arg(long, value_name = "REGEX", requires = "symbols_json")]
+    symbols_json_filter: Option<String>,
+
+    /// Generate a linker map and write its input sections, sorted by the given
+    /// criterion, to build/<project>.sorted.map
+    #[arg(long, value_enum, value_name = "ORDER")]
+    map_sort: Option<MapSort>,
+
+    /// Write a linker map to build/<project>.map (default; see --output-map-path)
+    #[arg(long, overrides_with = "no_map")]
+    map: bool,
+
+    /// Do not generate the linker map
+    #[arg(long, overrides_with = "map", conflicts_with_all = ["stack_guard", "map_sort", "output_map_path"])]
+    no_map: bool,
+
+    /// Write the linker map to PATH instead of build/<project>.map.
+    /// A directory gets <project>.map inside it; defaults to `ecos_map_output_dir`
+    /// from [package.metadata.ecos]
+    #[arg(long, value_name = "PATH")]
+    output_map_path: Option<PathBuf>,
+
+    /// Profile-guided optimisation, step 2: build with the merged profile
+    /// (-C profile-use). Step 1 is --profile-generate, then run the firmware and
+    /// merge the raw profiles with `llvm-profdata merge -o <FILE> <DIR>`
+    #[arg(long, value_name = "FILE", conflicts_with = "profile_generate")]
+    profile_data: Option<PathBuf>,
+
+    /// Profile-guided optimisation, step 1: instrument the build to write raw
+    /// profiles to DIR (-C profile-generate, defaults to build/pgo)
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "build/pgo")]
+    profile_generate: Option<PathBuf>,
+
+    /// Inject a cfg flag, as KEY or KEY=VALUE (repeatable, added to the
+    /// ecos_default_cfgs list in Cargo.toml)
+    #[arg(long = "cfg", value_name = "KEY[=VALUE]", value_parser = parse_cfg)]
+    cfgs: Vec<String>,
+
+    /// Abort the cargo build (SIGTERM, then SIGKILL) if it runs longer than SECONDS
+    #[arg(long, value_name = "SECONDS")]
+    max_build_time: Option<u64>,
+
+    /// Never let rustup install a missing toolchain implicitly: pin RUSTUP_TOOLCHAIN
+    /// and fail with the install command instead (useful in CI)
+    #[arg(long)]
+    no_rustup_check: bool,
+
+    /// Print the cfg values active for the target (with the injected RUSTFLAGS)
+    /// and exit without building
+    #[arg(long)]
+    dump_cfg: bool,
+
+    /// Cargo target directory to use instead of target/ (passed to cargo build --target-dir)
+    #[arg(long, value_name = "PATH", value_parser = parse_target_dir)]
+    target_dir: Option<PathBuf>,
+
+    /// Pass --locked to cargo: fail if Cargo.lock is missing or out of date
+    #[arg(long)]
+    cargo_locked: bool,
+
+    /// Pass --frozen to cargo: like --cargo-locked, and also forbid network access
+    #[arg(long)]
+    cargo_frozen: bool,
+
+    /// Whitespace-separated KEY=VALUE environment variables for the cargo build (and so
+    /// for build.rs), e.g. "ECOS_SDK_HOME=/opt/sdk FOO=1". The standard ECOS build.rs
+    /// reads ECOS_SDK_HOME (ECOS_FIRMWARE_ID / ECOS_GIT_HASH come from --firmware-id
+    /// and --embed-git-hash)
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true, value_parser = crate::cmd::parse_env_pairs)]
+    build_script_args: Option<crate::cmd::EnvPairs>,
+
+    /// Extra C compiler flags for C sources compiled by build.rs, e.g. "-DDEBUG -O0"
+    /// (passed as ECOS_CFLAGS, which the ECOS build.rs template appends to cc::Build)
+    #[arg(long, value_name = "FLAGS", allow_hyphen_values = true)]
+    cflags: Option<String>,
+
+    /// Do not append this build to build/size_history.csv
+    #[arg(long)]
+    no_size_history: bool,
+
+    /// Print the binary sizes of the last 10 recorded builds as a sparkline and exit
+    #[arg(long)]
+    size_trend: bool,
+
+    /// Print the dependency tree for the target and highlight crates that have
+    /// the `std` feature enabled, then exit without building
+    #[arg(long)]
+    dependency_tree: bool,
+
+    /// After building, print the enabled Cargo features of every crate compiled for
+    /// the target (from cargo's JSON messages); std / alloc are highlighted
+    #[arg(long)]
+    features_report: bool,
+
+    /// Print the linker (riscv64-unknown-elf-ld) version and exit without building
+    #[arg(long)]
+    ld_version: bool,
+
+    /// Print the riscv64-unknown-elf-objcopy version and exit without building
+    #[arg(long)]
+    objcopy_version: bool,
+
+    /// Print the riscv64-unknown-elf-objdump version and exit without building
+    #[arg(long)]
+    objdump_version: bool,
+
     /// Additional arguments to pass to cargo build
     #[arg(last = true, num_args = 0.., allow_hyphen_values = true)]
     args: Vec<String>,
@@ -26,16 +362,109 @@ pub struct BuildCommand {
 
 impl Command for BuildCommand {
     fn execute(&self) -> Result<()> {
+        // 只影响 build 命令本身，其他命令仍按终端自动检测
+        if self.no_color_output {
+            console::set_colors_enabled(false);
+            console::set_colors_enabled_stderr(false);
+        }
+
+        if self.ld_version || self.objcopy_version || self.objdump_version {
+            return self.print_tool_versions();
+        }
+
+        if self.dependency_tree {
+            return print_dependency_tree(&crate::cmd::find_project_root()?);
+        }
+
+        if self.features_matrix {
+            return self.run_features_matrix();
+        }
+
+        if self.dump_cfg {
+            return self.dump_cfg();
+        }
+
+        if self.size_trend {
+            return print_size_trend(&crate::cmd::find_project_root()?);
+        }
+
+        let started = Instant::now();
+
+        if !self.compact_report {
+            return self.build(started);
+        }
+
+        COMPACT_REPORT.store(true, Ordering::Relaxed);
+        match self.build(started).and_then(|_| self.compact_summary()) {
+            Ok(summary) => {
+                println!("cargo-ecos: OK | {}", summary);
+                Ok(())
+            }
+            Err(e) => {
+                println!(
+                    "cargo-ecos: FAIL | {}",
+                    e.to_string().lines().next().unwrap_or_default()
+                );
+                Err(e)
+            }
+        }
+    }
+}
+
+impl BuildCommand {
+    /// 打印所请求的工具链版本信息（--ld-version 等），不进行构建
+    fn print_tool_versions(&self) -> Result<()> {
+        let tools = [
+            (self.ld_version, "riscv64-unknown-elf-ld"),
+            (self.objcopy_version, "riscv64-unknown-elf-objcopy"),
+            (self.objdump_version, "riscv64-unknown-elf-objdump"),
+        ];
+
+        for (_, tool) in tools.iter().filter(|(wanted, _)| *wanted) {
+            let output = StdCommand::new(tool)
+                .arg("--version")
+                .output()
+                .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", tool, e))?;
+
+            if !output.status.success() {
+                return Err(anyhow::anyhow!("'{} --version' failed", tool));
+            }
+
+            print!("{}", String::from_utf8_lossy(&output.stdout));
+        }
+
+        Ok(())
+    }
+
+    fn build(&self, started: Instant) -> Result<()> {
+        // LTO 需要 .rlib 中的 bitcode，rustc 不接受同时指定 embed-bitcode=no 和 -C lto
+        if self.no_embed_bitcode && self.lto.is_some() {
+            return Err(anyhow::anyhow!(
+                "--no-embed-bitcode cannot be used with --lto: LTO needs the embedded bitcode"
+            ));
+        }
+
         // 找到项目根目录
         let project_root = crate::cmd::find_project_root()?;
         std::env::set_current_dir(&project_root)?;
 
-        println!("{} Building ECOS firmware...", style("🔨").cyan());
+        progress!("{} Building ECOS firmware...", style("🔨").cyan());
+
+        let cargo_profile = self.cargo_profile();
+        if self.no_embed_bitcode
+            && self.lto.is_none()
+            && profile_enables_lto(&project_root, cargo_profile)?
+        {
+            return Err(anyhow::anyhow!(
+                "--no-embed-bitcode cannot be used because [profile.{}] in Cargo.toml enables lto",
+                cargo_profile
+            ));
+        }
 
         // 检查 autoconf.h 是否存在
         let autoconf_h = project_root.join("include/generated/autoconf.h");
         if !autoconf_h.exists() {
-            println!(
+            progress!(
                 "{} {}",
                 style("❌").red(),
                 style("include/generated/autoconf.h not found").bold()
@@ -49,137 +478,911 @@ impl Command for BuildCommand {
         check_environment()?;
         let sdk_home = crate::cmd::check_sdk_home()?;
 
-        let mut cargo_cmd = StdCommand::new("cargo");
-        cargo_cmd.arg("build");
+        let project_name = crate::cmd::extract_project_name(&project_root)?;
+        let bin_path = project_root
+            .join("build")
+            .join(format!("{}.bin", project_name));
 
-        if self.release {
-            cargo_cmd.arg("--release");
-            println!("  Mode: {}", style("release").bold());
-        } else {
-            println!("  Mode: {}", style("debug").bold());
-        }
+        // 记录上一次构建的体积，用于对比 panic=abort 带来的变化
+        let previous_size = std::fs::metadata(&bin_path).map(|m| m.len()).ok();
+        let panic_abort_marker = project_root.join("build").join(PANIC_ABORT_MARKER);
+        let panic_abort_newly_enabled = self.panic_abort && !panic_abort_marker.exists();
 
-        for arg in &self.args {
-            cargo_cmd.arg(arg);
+        if self.panic_abort && profile_sets_panic_abort(&project_root)? {
+            progress!(
+                "{} Cargo.toml already sets panic = \"abort\" in a profile, --panic-abort is redundant",
+                style("⚠️").yellow()
+            );
         }
 
-        let status = cargo_cmd
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
+        if self.lto == Some(LtoMode::Fat) && self.codegen_units.is_some_and(|units| units > 1) {
+            progress!(
+                "{} --lto fat merges all code into a single unit, --codegen-units has no effect on the final binary",
+                style("⚠️").yellow()
+            );
+        }
 
-        if !status.success() {
-            return Err(anyhow::anyhow!("Cargo build failed"));
+        if self.update_known_nostd {
+            update_known_nostd(&project_root)?;
+        }
+        if self.check_deps {
+            check_nostd_deps(&project_root, &project_name)?;
         }
 
-        self.run_postbuild(&project_root)?;
+        let mut cargo_cmd = StdCommand::new("cargo");
 
-        if !self.no_mem_report {
-            self.generate_memory_report(&project_root, &sdk_home)?;
+        // rust-toolchain.toml 已指定 nightly 时由 rustup 自动处理
+        if toolchain_file_is_nightly(&project_root) {
+            progress!(
+                "  Toolchain: {} (rust-toolchain.toml)",
+                style("nightly").bold()
+            );
+        } else if self.nightly {
+            check_nightly_installed()?;
+            cargo_cmd.arg("+nightly");
+            progress!("  Toolchain: {}", style("nightly").bold());
         }
 
-        println!("✅ {} Build completed successfully!", style("ECOS").green());
+        if self.max_stack_depth.is_some()
+            && !self.nightly
+            && !toolchain_file_is_nightly(&project_root)
+        {
+            return Err(anyhow::anyhow!(
+                "--max-stack-depth needs -Z emit-stack-sizes. Use --nightly or a nightly rust-toolchain.toml"
+            ));
+        }
 
-        if self.sections {
-            self.print_sections_info(&project_root)?;
+        if self.no_rustup_check {
+            let toolchain = resolve_installed_toolchain(&project_root, self.nightly)?;
+            cargo_cmd
+                .env("RUSTUP_TOOLCHAIN", &toolchain)
+                .env("RUSTUP_AUTO_INSTALL", "0");
         }
 
-        Ok(())
-    }
-}
+        // --no-link：rustc 会合并多个 --emit，cargo build 总会带上 link，
+        // 因此改用 check profile 下的 cargo rustc，只给固件 crate 追加 obj 输出
+        if self.no_link {
+            cargo_cmd.args(["rustc", "--bin", &project_name]);
+        } else {
+            cargo_cmd.arg("build");
+        }
 
-impl BuildCommand {
-    fn run_postbuild(&self, project_root: &Path) -> Result<()> {
-        println!("{} Running post-build steps...", style("🛠️").cyan());
+        let mut extra_rustflags = self.extra_rustflags();
+        if let Some(flags) = self.arch_flags(&project_root)? {
+            extra_rustflags.push("-C".to_string());
+            extra_rustflags.push(format!("target-feature={}", flags));
+        }
+        extra_rustflags.extend(self.cfg_flags(&project_root)?);
+        if let Some(linker) = &self.linker {
+            extra_rustflags.extend(linker_flags(linker)?);
+            progress!("  Linker: {}", style(linker).bold());
+        }
+        if let Some(profile) = &self.profile_data {
+            // 依赖在各自的目录中编译，需要绝对路径
+            let profile = std::fs::canonicalize(project_root.join(profile)).map_err(|_| {
+                anyhow::anyhow!("Profile data file not found: {}", profile.display())
+            })?;
+            if !profile.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Profile data must be a file: {}",
+                    profile.display()
+                ));
+            }
+            progress!("  PGO: using {}", style(profile.display()).dim());
+            extra_rustflags.push("-C".to_string());
+            extra_rustflags.push(format!("profile-use={}", profile.display()));
+        }
+        if let Some(dir) = &self.profile_generate {
+            let dir = project_root.join(dir);
+            std::fs::create_dir_all(&dir)?;
+            progress!(
+                "  PGO: instrumented build, raw profiles go to {}",
+                style(dir.display()).dim()
+            );
+            extra_rustflags.push("-C".to_string());
+            extra_rustflags.push(format!("profile-generate={}", dir.display()));
+        }
+        if self.stack_guard.is_some() {
+            let fragment = write_stack_guard_script(&project_root)?;
+            extra_rustflags.push("-C".to_string());
+            extra_rustflags.push(format!("link-arg=-Wl,-T,{}", fragment.display()));
+        }
+        let map_path = self.map_path(&project_root, &project_name);
+        if !self.no_map && !self.no_link {
+            // --stack-guard / --map-sort 也依赖 map 文件
+            if let Some(parent) = map_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            extra_rustflags.push("-C".to_string());
+            extra_rustflags.push(format!("link-arg=-Wl,-Map={}", map_path.display()));
+        }
+        if !extra_rustflags.is_empty() {
+            let mut rustflags = base_rustflags(&project_root);
+            rustflags.extend(extra_rustflags);
+            progress!("  RUSTFLAGS: {}", style(rustflags.join(" ")).dim());
+            // 用 \x1f 分隔，参数中的空格（例如项目路径）不会被拆开
+            cargo_cmd.env(
+                "CARGO_ENCODED_RUSTFLAGS",
+                rustflags.join(RUSTFLAGS_SEPARATOR),
+            );
+        }
 
-        let profile = if self.release { "release" } else { "debug" };
+        if self.no_link {
+            cargo_cmd.args(["--profile", "check"]);
+            progress!("  Mode: {}", style("check (no link)").bold());
+        } else if self.release {
+            cargo_cmd.arg("--release");
+            progress!("  Mode: {}", style("release").bold());
+        } else {
+            progress!("  Mode: {}", style("debug").bold());
+        }
 
-        // 读取项目名称
-        let project_name = extract_project_name(project_root)?;
+        if let Some(level) = &self.opt_level {
+            if self.release {
+                progress!(
+                    "  Opt-level: {} (overrides the release profile)",
+                    style(level).bold()
+                );
+            } else {
+                progress!("  Opt-level: {}", style(level).bold());
+            }
+        }
 
-        // ELF 文件路径
-        let elf = project_root.join(format!(
-            "target/riscv32imac-unknown-none-elf/{}/{}",
-            profile, project_name
-        ));
-        if !elf.exists() {
-            return Err(anyhow::anyhow!("ELF file not found: {}", elf.display()));
+        if let Some(firmware_id) = &self.firmware_id {
+            let firmware_id = if firmware_id.is_empty() {
+                git_short_hash(&project_root).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Could not determine git commit hash, pass --firmware-id <STRING> explicitly"
+                    )
+                })?
+            } else {
+                firmware_id.clone()
+            };
+            progress!("  Firmware ID: {}", style(&firmware_id).bold());
+            cargo_cmd.env("ECOS_FIRMWARE_ID", firmware_id);
         }
 
-        let out_dir = project_root.join("build");
-        std::fs::create_dir_all(&out_dir)?;
+        if self.embed_git_hash {
+            let hash = git_short_hash(&project_root).ok_or_else(|| {
+                anyhow::anyhow!("Could not determine git commit hash (is this a git repository?)")
+            })?;
+            if git_tree_dirty(&project_root) {
+                progress!(
+                    "{} Working tree has uncommitted changes, {} does not describe this build exactly",
+                    style("⚠️").yellow(),
+                    hash
+                );
+            }
+            ensure_version_rs(&project_root)?;
+            progress!("  Git hash: {}", style(&hash).bold());
+            cargo_cmd.env("ECOS_GIT_HASH", hash);
+        }
 
-        // 清理旧文件
-        let _ = std::fs::remove_file(out_dir.join(format!("{}.bin", project_name)));
-        let _ = std::fs::remove_file(out_dir.join(format!("{}.hex", project_name)));
-        let _ = std::fs::remove_file(out_dir.join(format!("{}.txt", project_name)));
+        if let Some(lto) = self.lto {
+            // 通过 CARGO_PROFILE_<PROFILE>_LTO 覆盖 profile 设置，只作用于最终产物
+            cargo_cmd.env(self.lto_env_var(), lto.as_str());
+            progress!("  LTO: {}", style(lto.as_str()).bold());
+        }
 
-        // objcopy 生成 bin 文件
-        println!("  📦 Generating binary file...");
-        let status = StdCommand::new("riscv64-unknown-elf-objcopy")
-            .args(&[
-                "-O",
-                "binary",
-                elf.to_str().unwrap(),
-                out_dir
-                    .join(format!("{}.bin", project_name))
-                    .to_str()
-                    .unwrap(),
-            ])
-            .status()?;
+        if self.timings || self.timings_open {
+            cargo_cmd.arg("--timings=html");
+        }
 
-        if !status.success() {
-            return Err(anyhow::anyhow!("Failed to generate binary file"));
+        if self.no_color_output {
+            cargo_cmd.args(["--color", "never"]);
         }
 
-        // objcopy 生成 hex 文件
-        println!("  🔢 Generating hex file...");
-        let status = StdCommand::new("riscv64-unknown-elf-objcopy")
-            .args(&[
-                "-O",
-                "verilog",
-                elf.to_str().unwrap(),
-                out_dir
-                    .join(format!("{}.hex", project_name))
-                    .to_str()
-                    .unwrap(),
-            ])
-            .status()?;
+        if let Some(envs) = &self.build_script_args {
+            for (key, value) in &envs.0 {
+                cargo_cmd.env(key, value);
+                progress!("  Env: {}={}", style(key).cyan(), value);
+            }
+        }
 
-        if !status.success() {
-            return Err(anyhow::anyhow!("Failed to generate hex file"));
+        // --max-stack-depth 需要 C 代码的 .su 文件
+        let cflags = match (&self.cflags, self.max_stack_depth) {
+            (Some(cflags), Some(_)) => Some(format!("{} -fstack-usage", cflags)),
+            (Some(cflags), None) => Some(cflags.clone()),
+            (None, Some(_)) => Some("-fstack-usage".to_string()),
+            (None, None) => None,
+        };
+        if let Some(cflags) = &cflags {
+            cargo_cmd.env("ECOS_CFLAGS", cflags);
+            progress!("  CFLAGS: {}", style(cflags).dim());
         }
 
-        // 修复 hex 文件地址
-        let hex_path = out_dir.join(format!("{}.hex", project_name));
-        let hex_content = std::fs::read_to_string(&hex_path)?;
-        let fixed_hex = hex_content.replace("@30000000", "@00000000");
-        std::fs::write(&hex_path, fixed_hex)?;
+        if self.cargo_locked || self.cargo_frozen {
+            let lockfile = workspace_root(&project_root)
+                .unwrap_or_else(|| project_root.clone())
+                .join("Cargo.lock");
+            if !lockfile.exists() {
+                return Err(anyhow::anyhow!(
+                    "{} requires a Cargo.lock, but {} does not exist.\n\
+                     Run 'cargo generate-lockfile' first and commit the lockfile.",
+                    if self.cargo_frozen {
+                        "--cargo-frozen"
+                    } else {
+                        "--cargo-locked"
+                    },
+                    lockfile.display()
+                ));
+            }
+            cargo_cmd.arg(if self.cargo_frozen {
+                "--frozen"
+            } else {
+                "--locked"
+            });
+        }
 
-        // objdump 生成反汇编
-        println!("  📝 Generating disassembly...");
-        let output = StdCommand::new("riscv64-unknown-elf-objdump")
-            .args(&["-d", elf.to_str().unwrap()])
-            .output()?;
+        if let Some(dir) = &self.target_dir {
+            cargo_cmd.arg("--target-dir").arg(dir);
+            progress!("  Target dir: {}", style(dir.display()).dim());
+        }
 
-        std::fs::write(out_dir.join(format!("{}.txt", project_name)), output.stdout)?;
+        if self.fail_on_warning || self.features_report {
+            cargo_cmd.arg(if self.no_color_output {
+                "--message-format=json"
+            } else {
+                "--message-format=json-diagnostic-rendered-ansi"
+            });
+        }
 
-        println!("{} Post-build steps completed", style("✅").green());
-        Ok(())
-    }
+        for arg in &self.args {
+            cargo_cmd.arg(arg);
+        }
+        if self.no_link {
+            cargo_cmd.args(["--", "--emit=dep-info,metadata,obj"]);
+        }
 
-    fn generate_memory_report(&self, project_root: &Path, sdk_home: &str) -> Result<()> {
-        println!("{} Generating memory usage report...", style("📊").cyan());
+        cargo_cmd.current_dir(&project_root);
 
-        let profile = if self.release { "release" } else { "debug" };
-        let project_name = extract_project_name(project_root)?;
-        let elf_path = project_root.join(format!(
-            "target/riscv32imac-unknown-none-elf/{}/{}",
-            profile, project_name
-        ));
+        let elf_path = self.elf_path(&project_root, &project_name);
+        self.run_build_hook("ecos_build_pre_hook", &project_root, &elf_path)?;
 
-        if !elf_path.exists() {
+        if self.fail_on_warning || self.features_report {
+            let CargoJsonRun {
+                status,
+                warnings,
+                features,
+            } = run_cargo_counting_warnings(&mut cargo_cmd, self.max_build_time)?;
+            if self.fail_on_warning && !warnings.is_empty() {
+                print_warning_summary(&warnings);
+            }
+            if self.features_report && status.success() {
+                print_features_report(&features);
+            }
+            if !status.success() {
+                let total: usize = warnings.values().sum();
+                return Err(if total > 0 {
+                    anyhow::anyhow!("Cargo build failed: {} warning(s) treated as errors", total)
+                } else {
+                    anyhow::anyhow!("Cargo build failed")
+                });
+            }
+        } else {
+            let mut child = cargo_cmd
+                .stdout(child_stdio())
+                .stderr(child_stdio())
+                .spawn()?;
+            let watchdog = BuildWatchdog::start(&child, self.max_build_time);
+            let status = child.wait()?;
+            watchdog.finish()?;
+
+            if !status.success() {
+                return Err(anyhow::anyhow!("Cargo build failed"));
+            }
+        }
+
+        if self.no_link {
+            progress!(
+                "✅ {} Compiled without linking (--no-link), no firmware generated",
+                style("ECOS").green()
+            );
+            return Ok(());
+        }
+
+        self.run_postbuild(&project_root)?;
+        self.run_build_hook("ecos_build_post_hook", &project_root, &elf_path)?;
+        self.check_binary_size(&project_root, &bin_path);
+
+        if let Some(size) = self.stack_guard {
+            report_stack_guard(&map_path, size)?;
+        }
+
+        if let Some(limit) = self.max_stack_depth {
+            let build_dir = self
+                .cargo_target_dir(&project_root)
+                .join(TARGET_TRIPLE)
+                .join(self.profile())
+                .join("build");
+            check_stack_depth(&elf_path, &build_dir, limit)?;
+        }
+
+        if let Some(order) = self.map_sort {
+            let sorted_path = project_root
+                .join("build")
+                .join(format!("{}.sorted.map", project_name));
+            write_sorted_map(&map_path, &sorted_path, order)?;
+        }
+
+        if self.panic_abort {
+            std::fs::write(&panic_abort_marker, "")?;
+            if panic_abort_newly_enabled
+                && let Some(previous_size) = previous_size
+                && let Ok(metadata) = std::fs::metadata(&bin_path)
+            {
+                print_size_change(previous_size, metadata.len());
+            }
+        } else {
+            let _ = std::fs::remove_file(&panic_abort_marker);
+        }
+
+        if !self.no_mem_report {
+            self.generate_memory_report(&project_root, &sdk_home)?;
+        }
+
+        if self.sections {
+            self.print_sections_info(&project_root)?;
+        }
+
+        if !self.skip_resources_section {
+            report_resources_section(&elf_path);
+        }
+
+        if self.symbols_json {
+            write_symbols_json(
+                &elf_path,
+                &project_root.join("build").join("symbols.json"),
+                self.symbols_json_filter.as_deref(),
+            )?;
+        }
+
+        if let Some(host_target) = &self.cross_check {
+            self.run_cross_check(&project_root, host_target)?;
+        }
+
+        if self.timings || self.timings_open {
+            self.report_timings(&project_root)?;
+        }
+
+        if !self.no_size_history {
+            self.record_size_history(&project_root, &project_name)?;
+        }
+
+        // 以 cargo 风格的一行汇总结束，便于在 CI 日志中 grep
+        let bin_size = std::fs::metadata(&bin_path).map(|m| m.len()).unwrap_or(0);
+        progress!(
+            "{} {} v{} [{}] {} in {:.2}s",
+            style("Built:").green().bold(),
+            project_name,
+            package_version(&project_root).unwrap_or_else(|| "0.0.0".to_string()),
+            if self.release { "release" } else { "debug" },
+            format_size(bin_size, DECIMAL),
+            started.elapsed().as_secs_f64()
+        );
+
+        Ok(())
+    }
+
+    /// --features-matrix：无 feature 的基线 + 每个 feature 单独构建一次，比较 .bin 体积
+    fn run_features_matrix(&self) -> Result<()> {
+        use rayon::prelude::*;
+
+        let project_root = crate::cmd::find_project_root()?;
+        if !project_root.join("include/generated/autoconf.h").exists() {
+            return Err(anyhow::anyhow!(
+                "Configuration not found. Run 'cargo ecos config' first."
+            ));
+        }
+        check_environment()?;
+        crate::cmd::check_sdk_home()?;
+
+        let project_name = crate::cmd::extract_project_name(&project_root)?;
+        let features = cargo_features(&project_root)?;
+        if features.is_empty() {
+            return Err(anyhow::anyhow!("No features defined in Cargo.toml"));
+        }
+
+        println!(
+            "{} Building {} feature combination(s) in parallel...",
+            style("🧮").cyan(),
+            features.len() + 1
+        );
+
+        // 每个组合使用独立的 target 目录，避免互相等待构建锁
+        let matrix_dir = self.cargo_target_dir(&project_root).join("features-matrix");
+        let rustflags = {
+            let mut flags = base_rustflags(&project_root);
+            flags.extend(self.extra_rustflags());
+            flags.join(RUSTFLAGS_SEPARATOR)
+        };
+
+        let mut combinations = vec![None];
+        combinations.extend(features.iter().map(Some));
+
+        let results: Vec<(Option<&String>, Result<u64>)> = combinations
+            .into_par_iter()
+            .map(|feature| {
+                let label = feature.map_or("baseline", |f| f.as_str());
+                let result = self.build_matrix_entry(
+                    &project_root,
+                    &project_name,
+                    &matrix_dir.join(label),
+                    feature,
+                    &rustflags,
+                );
+                (feature, result)
+            })
+            .collect();
+
+        let baseline = match &results[0].1 {
+            Ok(size) => *size,
+            Err(e) => return Err(anyhow::anyhow!("Baseline build failed: {}", e)),
+        };
+
+        let mut rows: Vec<(&String, i64, u64)> = Vec::new();
+        for (feature, result) in &results[1..] {
+            let feature = feature.expect("only the baseline has no feature");
+            match result {
+                Ok(size) => rows.push((feature, *size as i64 - baseline as i64, *size)),
+                Err(e) => println!("  {} {}: {}", style("❌").red(), style(feature).bold(), e),
+            }
+        }
+        rows.sort_by_key(|(_, diff, _)| std::cmp::Reverse(*diff));
+
+        let width = rows
+            .iter()
+            .map(|(f, _, _)| f.len())
+            .max()
+            .unwrap_or(0)
+            .max(8);
+        println!();
+        println!(
+            "  {:<width$}  {:>12}  {:>12}",
+            "Feature", "Size", "Δ baseline"
+        );
+        println!(
+            "  {:<width$}  {:>12}  {:>12}",
+            "(none)",
+            format_size(baseline, DECIMAL),
+            "-"
+        );
+        for (feature, diff, size) in rows {
+            let diff_text = format!(
+                "{}{}",
+                if diff < 0 { "-" } else { "+" },
+                format_size(diff.unsigned_abs(), DECIMAL)
+            );
+            let diff_text = if diff > 0 {
+                style(diff_text).yellow()
+            } else {
+                style(diff_text).green()
+            };
             println!(
+                "  {:<width$}  {:>12}  {:>12}",
+                feature,
+                format_size(size, DECIMAL),
+                diff_text
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 以指定 feature 构建一次，返回 .bin 的体积
+    fn build_matrix_entry(
+        &self,
+        project_root: &Path,
+        project_name: &str,
+        target: &Path,
+        feature: Option<&String>,
+        rustflags: &str,
+    ) -> Result<u64> {
+        let mut cargo_cmd = StdCommand::new("cargo");
+        cargo_cmd
+            .args(["build", "--no-default-features"])
+            .env("CARGO_TARGET_DIR", target)
+            .env("CARGO_ENCODED_RUSTFLAGS", rustflags)
+            .current_dir(project_root);
+        if let Some(feature) = feature {
+            cargo_cmd.args(["--features", feature]);
+        }
+        if self.release {
+            cargo_cmd.arg("--release");
+        }
+
+        let output = cargo_cmd.output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "{}",
+                stderr
+                    .lines()
+                    .find(|line| line.starts_with("error"))
+                    .unwrap_or("build failed")
+            ));
+        }
+
+        let elf = target
+            .join(TARGET_TRIPLE)
+            .join(self.profile())
+            .join(project_name);
+        let bin = target.join(format!("{}.bin", project_name));
+        let status = StdCommand::new("riscv64-unknown-elf-objcopy")
+            .args(["-O", "binary"])
+            .arg(&elf)
+            .arg(&bin)
+            .status()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("objcopy failed for {}", elf.display()));
+        }
+
+        Ok(std::fs::metadata(&bin)?.len())
+    }
+
+    /// --compact-report 的汇总信息
+    fn compact_summary(&self) -> Result<String> {
+        let project_root = crate::cmd::find_project_root()?;
+        let project_name = crate::cmd::extract_project_name(&project_root)?;
+        let bin_path = project_root
+            .join("build")
+            .join(format!("{}.bin", project_name));
+        let size = std::fs::metadata(&bin_path)?.len();
+
+        Ok(format!(
+            "project={} profile={} size={}",
+            project_name,
+            self.profile(),
+            size
+        ))
+    }
+
+    /// --arch-flags，未指定时读取 ecos_target_features
+    fn arch_flags(&self, project_root: &Path) -> Result<Option<String>> {
+        match &self.arch_flags {
+            Some(flags) => Ok(Some(flags.clone())),
+            None => crate::cmd::ecos_metadata_str(project_root, "ecos_target_features")
+                .map(|flags| {
+                    parse_arch_flags(&flags)
+                        .map_err(|e| anyhow::anyhow!("Invalid ecos_target_features: {}", e))
+                })
+                .transpose(),
+        }
+    }
+
+    /// ecos_default_cfgs 与 --cfg 对应的 rustc 参数
+    fn cfg_flags(&self, project_root: &Path) -> Result<Vec<String>> {
+        let defaults = match crate::cmd::ecos_metadata_value(project_root, "ecos_default_cfgs") {
+            Some(value) => value
+                .as_array()
+                .ok_or_else(|| anyhow::anyhow!("ecos_default_cfgs must be an array of strings"))?
+                .iter()
+                .map(|cfg| {
+                    cfg.as_str()
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("ecos_default_cfgs must be an array of strings")
+                        })
+                        .and_then(|cfg| {
+                            parse_cfg(cfg)
+                                .map_err(|e| anyhow::anyhow!("Invalid ecos_default_cfgs: {}", e))
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(defaults
+            .into_iter()
+            .chain(self.cfgs.iter().cloned())
+            .flat_map(|cfg| {
+                // 同时声明 check-cfg，避免 unexpected_cfgs 警告
+                let check = match cfg.split_once('=') {
+                    Some((key, _)) => format!("cfg({},values(any()))", key),
+                    None => format!("cfg({})", cfg),
+                };
+                ["--cfg".to_string(), cfg, "--check-cfg".to_string(), check]
+            })
+            .collect())
+    }
+
+    /// 向 build/size_history.csv 追加本次构建的大小，文件不存在时先写表头
+    fn record_size_history(&self, project_root: &Path, project_name: &str) -> Result<()> {
+        use std::io::Write;
+
+        let build_dir = project_root.join("build");
+        let file_size = |ext: &str| {
+            std::fs::metadata(build_dir.join(format!("{}.{}", project_name, ext)))
+                .map(|m| m.len().to_string())
+                .unwrap_or_default()
+        };
+
+        let history = build_dir.join(SIZE_HISTORY_FILE);
+        let is_new = !history.exists();
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&history)?;
+        if is_new {
+            writeln!(file, "{}", SIZE_HISTORY_HEADER)?;
+        }
+        writeln!(
+            file,
+            "{},{},{},{},{}",
+            chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            git_short_hash(project_root).unwrap_or_default(),
+            self.profile(),
+            file_size("bin"),
+            file_size("hex")
+        )?;
+
+        Ok(())
+    }
+
+    /// --dump-cfg：打印当前目标与 RUSTFLAGS 下生效的 cfg
+    fn dump_cfg(&self) -> Result<()> {
+        let project_root = crate::cmd::find_project_root()?;
+
+        let mut rustflags = base_rustflags(&project_root);
+        rustflags.extend(self.extra_rustflags());
+        if let Some(flags) = self.arch_flags(&project_root)? {
+            rustflags.push("-C".to_string());
+            rustflags.push(format!("target-feature={}", flags));
+        }
+        rustflags.extend(self.cfg_flags(&project_root)?);
+
+        let output = StdCommand::new("rustc")
+            .args(["--print", "cfg", "--target", TARGET_TRIPLE])
+            .args(&rustflags)
+            .current_dir(&project_root)
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "rustc --print cfg failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut cfgs: Vec<&str> = stdout.lines().collect();
+        cfgs.sort_unstable();
+
+        println!(
+            "{} Active cfg for {}:",
+            style("⚙️").cyan(),
+            style(TARGET_TRIPLE).bold()
+        );
+        for cfg in cfgs {
+            match cfg.split_once('=') {
+                Some((key, value)) => println!("  {}={}", style(key).cyan(), style(value).green()),
+                None => println!("  {}", style(cfg).cyan()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 根据命令行参数生成需要额外注入的 RUSTFLAGS
+    fn extra_rustflags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+
+        if self.panic_abort {
+            flags.push("-C".to_string());
+            flags.push("panic=abort".to_string());
+        }
+
+        if let Some(addr) = self.addr {
+            flags.push("-C".to_string());
+            flags.push(format!(
+                "link-arg=-Wl,--defsym=ECOS_LOAD_ADDR={:#010x}",
+                addr
+            ));
+        }
+
+        if let Some(level) = &self.opt_level {
+            flags.push("-C".to_string());
+            flags.push(format!("opt-level={}", level));
+        }
+
+        if let Some(units) = self.codegen_units {
+            flags.push("-C".to_string());
+            flags.push(format!("codegen-units={}", units));
+        }
+
+        if self.no_embed_bitcode {
+            flags.push("-C".to_string());
+            flags.push("embed-bitcode=no".to_string());
+        }
+
+        if self.fail_on_warning {
+            flags.push("-D".to_string());
+            flags.push("warnings".to_string());
+        }
+
+        if self.max_stack_depth.is_some() {
+            flags.push("-Z".to_string());
+            flags.push("emit-stack-sizes".to_string());
+        }
+
+        if let Some(size) = self.stack_guard {
+            flags.push("-C".to_string());
+            flags.push(format!(
+                "link-arg=-Wl,--defsym,ECOS_STACK_GUARD_SIZE={:#x}",
+                size
+            ));
+        }
+
+        flags
+    }
+
+    fn profile(&self) -> &'static str {
+        if self.release { "release" } else { "debug" }
+    }
+
+    /// cargo 中的 profile 名称（debug 构建对应 dev）
+    fn cargo_profile(&self) -> &'static str {
+        if self.release { "release" } else { "dev" }
+    }
+
+    /// 覆盖当前 profile LTO 设置的环境变量
+    fn lto_env_var(&self) -> String {
+        format!("CARGO_PROFILE_{}_LTO", self.cargo_profile().to_uppercase())
+    }
+
+    /// 本次构建使用的 cargo target 目录（--target-dir 优先）
+    fn cargo_target_dir(&self, project_root: &Path) -> PathBuf {
+        self.target_dir
+            .clone()
+            .unwrap_or_else(|| target_dir(project_root))
+    }
+
+    /// 链接 map 文件路径：--output-map-path > ecos_map_output_dir > build/<project>.map，
+    /// 指向目录（已存在或以 / 结尾）时在其中写 <project>.map
+    fn map_path(&self, project_root: &Path, project_name: &str) -> PathBuf {
+        let file_name = format!("{}.map", project_name);
+        if let Some(path) = &self.output_map_path {
+            let is_dir = path.to_string_lossy().ends_with(std::path::is_separator)
+                || project_root.join(path).is_dir();
+            return if is_dir {
+                project_root.join(path).join(file_name)
+            } else {
+                project_root.join(path)
+            };
+        }
+
+        crate::cmd::ecos_metadata_str(project_root, "ecos_map_output_dir")
+            .map(|dir| project_root.join(dir).join(&file_name))
+            .unwrap_or_else(|| project_root.join("build").join(&file_name))
+    }
+
+    /// ELF 文件的绝对路径（不依赖当前工作目录）
+    fn elf_path(&self, project_root: &Path, project_name: &str) -> PathBuf {
+        self.cargo_target_dir(project_root)
+            .join(TARGET_TRIPLE)
+            .join(self.profile())
+            .join(project_name)
+    }
+
+    fn run_postbuild(&self, project_root: &Path) -> Result<()> {
+        progress!("{} Running post-build steps...", style("🛠️").cyan());
+
+        // 读取项目名称
+        let project_name = crate::cmd::extract_project_name(project_root)?;
+
+        // ELF 文件路径
+        let elf = self.elf_path(project_root, &project_name);
+        if !elf.exists() {
+            return Err(anyhow::anyhow!("ELF file not found: {}", elf.display()));
+        }
+
+        let out_dir = project_root.join("build");
+        std::fs::create_dir_all(&out_dir)?;
+
+        // 清理旧文件
+        let _ = std::fs::remove_file(out_dir.join(format!("{}.bin", project_name)));
+        let _ = std::fs::remove_file(out_dir.join(format!("{}.hex", project_name)));
+        let _ = std::fs::remove_file(out_dir.join(format!("{}.txt", project_name)));
+        let _ = std::fs::remove_file(out_dir.join(format!("{}.srec", project_name)));
+
+        // objcopy 生成 bin 文件
+        progress!("  📦 Generating binary file...");
+        let status = StdCommand::new("riscv64-unknown-elf-objcopy")
+            .args([
+                "-O",
+                "binary",
+                elf.to_str().unwrap(),
+                out_dir
+                    .join(format!("{}.bin", project_name))
+                    .to_str()
+                    .unwrap(),
+            ])
+            .status()?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("Failed to generate binary file"));
+        }
+
+        let load_addr = self.addr.unwrap_or(DEFAULT_LOAD_ADDR);
+
+        // objcopy 生成 hex 文件
+        if !self.no_hex_output {
+            progress!("  🔢 Generating hex file...");
+            let status = StdCommand::new("riscv64-unknown-elf-objcopy")
+                .args([
+                    "-O",
+                    "verilog",
+                    elf.to_str().unwrap(),
+                    out_dir
+                        .join(format!("{}.hex", project_name))
+                        .to_str()
+                        .unwrap(),
+                ])
+                .status()?;
+
+            if !status.success() {
+                return Err(anyhow::anyhow!("Failed to generate hex file"));
+            }
+
+            // 修复 hex 文件地址（以加载地址为基准）
+            let hex_path = out_dir.join(format!("{}.hex", project_name));
+            let hex_content = std::fs::read_to_string(&hex_path)?;
+            std::fs::write(&hex_path, rebase_hex_markers(&hex_content, load_addr))?;
+        }
+
+        // objcopy 生成 S-record 文件
+        if self.srec {
+            progress!("  🧾 Generating S-record file...");
+            let srec_path = out_dir.join(format!("{}.srec", project_name));
+            let status = StdCommand::new("riscv64-unknown-elf-objcopy")
+                .args([
+                    "-O",
+                    "srec",
+                    elf.to_str().unwrap(),
+                    srec_path.to_str().unwrap(),
+                ])
+                .status()?;
+
+            if !status.success() {
+                return Err(anyhow::anyhow!("Failed to generate S-record file"));
+            }
+
+            let srec_content = std::fs::read_to_string(&srec_path)?;
+            std::fs::write(&srec_path, rebase_srec(&srec_content, load_addr))?;
+        }
+
+        // objdump 生成反汇编
+        if !self.no_asm_output {
+            progress!("  📝 Generating disassembly...");
+            let objdump_flags = self
+                .objdump_flags
+                .clone()
+                .or_else(|| crate::cmd::ecos_metadata_str(project_root, "ecos_objdump_flags"))
+                .unwrap_or_default();
+            let output = StdCommand::new("riscv64-unknown-elf-objdump")
+                .arg("-d")
+                .args(objdump_flags.split_whitespace())
+                .arg(&elf)
+                .output()?;
+
+            std::fs::write(out_dir.join(format!("{}.txt", project_name)), output.stdout)?;
+        }
+
+        let map_path = self.map_path(project_root, &project_name);
+        if !self.no_map && map_path.exists() {
+            progress!("  🗺️  Linker map: {}", style(map_path.display()).dim());
+        }
+
+        progress!("{} Post-build steps completed", style("✅").green());
+        Ok(())
+    }
+
+    fn generate_memory_report(&self, project_root: &Path, sdk_home: &str) -> Result<()> {
+        progress!("{} Generating memory usage report...", style("📊").cyan());
+
+        let project_name = crate::cmd::extract_project_name(project_root)?;
+        let elf_path = self.elf_path(project_root, &project_name);
+
+        if !elf_path.exists() {
+            progress!(
                 "{} ELF file not found, skipping memory report",
                 style("⚠️").yellow()
             );
@@ -209,19 +1412,19 @@ impl BuildCommand {
                 .arg("-f")
                 .arg(&temp_makefile)
                 .arg("report")
-                .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit())
+                .stdout(child_stdio())
+                .stderr(child_stdio())
                 .status()?;
 
             // 清理临时文件
             let _ = std::fs::remove_file(&temp_makefile);
 
             if !status.success() {
-                println!("{} Memory report generation failed", style("⚠️").yellow());
+                progress!("{} Memory report generation failed", style("⚠️").yellow());
             }
         } else {
-            println!("{} mem_report.mk not found in SDK", style("⚠️").yellow());
-            println!("  Expected at: {}", mem_report_mk.display());
+            progress!("{} mem_report.mk not found in SDK", style("⚠️").yellow());
+            progress!("  Expected at: {}", mem_report_mk.display());
         }
 
         // 复制 sections.info 到 build/ 目录
@@ -232,95 +1435,1901 @@ impl BuildCommand {
         if sections_source.exists() {
             let sections_dest = out_dir.join("sections.info");
             if let Err(e) = std::fs::copy(&sections_source, &sections_dest) {
-                println!(
+                progress!(
                     "{} Failed to copy sections.info: {}",
                     style("⚠️").yellow(),
                     e
                 );
             } else {
-                println!("{} Copied sections.info to build ...", style("✅").green());
+                progress!("{} Copied sections.info to build ...", style("✅").green());
             }
         } else {
-            println!(
+            progress!(
                 "{} sections.info not found at expected location",
                 style("⚠️").yellow()
             );
-            println!("  Expected: {}", sections_source.display());
+            progress!("  Expected: {}", sections_source.display());
         }
 
         Ok(())
     }
 
-    /// 打印 sections.info 文件内容
-    fn print_sections_info(&self, project_root: &Path) -> Result<()> {
-        println!("\n{} Sections information:", style("📄").cyan());
-        println!("{}", "-".repeat(80));
+    /// 执行 Cargo.toml 中配置的构建钩子（ecos_build_pre_hook / ecos_build_post_hook）
+    fn run_build_hook(&self, key: &str, project_root: &Path, elf_path: &Path) -> Result<()> {
+        let Some(command) = crate::cmd::ecos_metadata_str(project_root, key) else {
+            return Ok(());
+        };
 
-        let path = project_root.join("build/sections.info");
+        progress!(
+            "{} Running {}: {}",
+            style("🪝").cyan(),
+            key,
+            style(&command).dim()
+        );
 
-        if path.exists() {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => {
-                    println!("📁 File: {}", path.display());
-                    println!("{}", content);
-                }
-                Err(e) => {
-                    println!(
-                        "{} Failed to read {}: {}",
-                        style("⚠️").yellow(),
-                        path.display(),
-                        e
-                    );
-                }
-            }
-        } else {
-            println!(
-                "{} sections.info not found at {}",
-                style("⚠️").yellow(),
-                path.display()
-            );
-            println!("Note: This file is generated only when memory report is enabled");
+        let status = crate::cmd::shell_command(&command)
+            .current_dir(project_root)
+            .env("PROJECT_ROOT", project_root)
+            .env("PROFILE", self.profile())
+            .env("ELF_PATH", elf_path)
+            .stdout(child_stdio())
+            .stderr(child_stdio())
+            .status()?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} failed with {}", key, status));
         }
 
-        println!("{}", "-".repeat(80));
         Ok(())
     }
-}
 
-fn extract_project_name(project_root: &Path) -> Result<String> {
-    let cargo_toml = project_root.join("Cargo.toml");
-    let content = std::fs::read_to_string(&cargo_toml)?;
+    /// 针对主机 target 运行 cargo check，捕获条件编译错误
+    fn run_cross_check(&self, project_root: &Path, host_target: &str) -> Result<()> {
+        let host_target = if host_target.is_empty() {
+            host_triple()?
+        } else {
+            host_target.to_string()
+        };
 
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("name =") {
-            let parts: Vec<&str> = trimmed.split('=').collect();
-            if parts.len() > 1 {
-                let name = parts[1].trim().trim_matches('"').trim_matches('\'');
-                return Ok(name.to_string());
-            }
-        }
-    }
+        progress!(
+            "{} Cross-checking for host target {}...",
+            style("🔍").cyan(),
+            style(&host_target).bold()
+        );
 
-    Err(anyhow::anyhow!(
-        "Could not extract project name from Cargo.toml"
-    ))
-}
+        let mut check_cmd = StdCommand::new("cargo");
+        check_cmd.args(["check", "--target", &host_target]);
+        if self.release {
+            check_cmd.arg("--release");
+        }
 
-fn check_environment() -> Result<()> {
-    // 检查 RISC-V 工具链
-    for tool in &[
-        "riscv64-unknown-elf-gcc",
-        "riscv64-unknown-elf-objcopy",
-        "riscv64-unknown-elf-objdump",
-    ] {
-        let status = StdCommand::new("which")
-            .arg(tool)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
+        let status = check_cmd
+            .current_dir(project_root)
+            .stdout(child_stdio())
+            .stderr(child_stdio())
             .status()?;
 
         if !status.success() {
+            return Err(anyhow::anyhow!(
+                "Firmware build succeeded, but host check failed for target '{}'",
+                host_target
+            ));
+        }
+
+        progress!(
+            "{} Host check passed for {}",
+            style("✅").green(),
+            host_target
+        );
+        Ok(())
+    }
+
+    /// 固件接近 Flash 容量时提示使用 LTO
+    fn check_binary_size(&self, project_root: &Path, bin_path: &Path) {
+        let Some(flash_size) =
+            crate::cmd::ecos_metadata_value(project_root, "ecos_flash_size_bytes")
+                .and_then(|v| v.as_integer())
+                .filter(|size| *size > 0)
+        else {
+            return;
+        };
+        let Ok(bin_size) = std::fs::metadata(bin_path).map(|m| m.len()) else {
+            return;
+        };
+
+        let usage = bin_size as f64 / flash_size as f64;
+        if usage < FLASH_SIZE_WARN_RATIO || self.lto.is_some() {
+            return;
+        }
+
+        progress!(
+            "{} Binary uses {:.0}% of flash ({} / {})",
+            style("⚠️").yellow(),
+            usage * 100.0,
+            format_size(bin_size, DECIMAL),
+            format_size(flash_size as u64, DECIMAL)
+        );
+        progress!(
+            "  {} Try {} to shrink it, LTO typically saves 10-30% (~{} - {})",
+            style("💡").yellow(),
+            style("cargo ecos build --lto thin --release").cyan(),
+            format_size(bin_size / 10, DECIMAL),
+            format_size(bin_size * 3 / 10, DECIMAL)
+        );
+    }
+
+    /// 输出构建耗时报告的位置
+    fn report_timings(&self, project_root: &Path) -> Result<()> {
+        let report = self
+            .cargo_target_dir(project_root)
+            .join("cargo-timings/cargo-timing.html");
+        if !report.exists() {
+            progress!(
+                "{} Timing report not found at {}",
+                style("⚠️").yellow(),
+                report.display()
+            );
+            return Ok(());
+        }
+
+        progress!(
+            "{} Timing report: {}",
+            style("⏱️").cyan(),
+            style(report.display()).dim()
+        );
+
+        if self.timings_open {
+            let opener = if cfg!(target_os = "macos") {
+                StdCommand::new("open").arg(&report).status()
+            } else if cfg!(windows) {
+                StdCommand::new("cmd")
+                    .args(["/C", "start", ""])
+                    .arg(&report)
+                    .status()
+            } else {
+                StdCommand::new("xdg-open").arg(&report).status()
+            };
+
+            if !matches!(opener, Ok(status) if status.success()) {
+                progress!("{} Failed to open timing report", style("⚠️").yellow());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 打印 sections.info 文件内容
+    fn print_sections_info(&self, project_root: &Path) -> Result<()> {
+        progress!("\n{} Sections information:", style("📄").cyan());
+        progress!("{}", "-".repeat(80));
+
+        let path = project_root.join("build/sections.info");
+
+        if path.exists() {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    progress!("📁 File: {}", path.display());
+                    progress!("{}", content);
+                }
+                Err(e) => {
+                    progress!(
+                        "{} Failed to read {}: {}",
+                        style("⚠️").yellow(),
+                        path.display(),
+                        e
+                    );
+                }
+            }
+        } else {
+            progress!(
+                "{} sections.info not found at {}",
+                style("⚠️").yellow(),
+                path.display()
+            );
+            progress!("Note: This file is generated only when memory report is enabled");
+        }
+
+        progress!("{}", "-".repeat(80));
+        Ok(())
+    }
+}
+
+/// 在 map 文件中查找栈保护区并输出其地址与大小
+fn report_stack_guard(map_path: &Path, size: u64) -> Result<()> {
+    let map = std::fs::read_to_string(map_path)?;
+    let symbol = |wanted: &str| {
+        map.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            let addr = parts.next()?.strip_prefix("0x")?;
+            let name = parts.next()?;
+            if name == wanted && parts.next() == Some("=") {
+                u64::from_str_radix(addr, 16).ok()
+            } else {
+                None
+            }
+        })
+    };
+
+    let (Some(start), Some(end)) = (
+        symbol(STACK_GUARD_START_SYMBOL),
+        symbol(STACK_GUARD_END_SYMBOL),
+    ) else {
+        return Err(anyhow::anyhow!(
+            "Stack guard region not found in {}: the linker did not apply {}",
+            map_path.display(),
+            STACK_GUARD_SCRIPT
+        ));
+    };
+    if end - start < size {
+        return Err(anyhow::anyhow!(
+            "Stack guard region at {:#010x} is {} bytes, expected {}",
+            start,
+            end - start,
+            size
+        ));
+    }
+
+    progress!(
+        "{} Stack guard: {:#010x}..{:#010x}, size {} bytes",
+        style("🛡️").cyan(),
+        start,
+        end,
+        end - start
+    );
+    Ok(())
+}
+
+/// 链接 map 中的一个输入段
+#[derive(Debug, PartialEq, Eq)]
+struct MapEntry {
+    section: String,
+    address: u64,
+    size: u64,
+    object: String,
+}
+
+/// 解析 GNU ld map 文件 "Linker script and memory map" 部分的输入段，
+/// 段名过长时地址/大小会换到下一行
+fn parse_map_entries(map: &str) -> Vec<MapEntry> {
+    let mut entries = Vec::new();
+    let mut pending_section: Option<String> = None;
+
+    let memory_map = map
+        .split_once("Linker script and memory map")
+        .map_or(map, |(_, rest)| rest);
+
+    for line in memory_map.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        // 输入段以一个空格开头，如 " .text.main  0x30000000  0x20 main.o"
+        let (section, fields) = if line.starts_with(" .") {
+            if parts.len() == 1 {
+                pending_section = Some(parts[0].to_string());
+                continue;
+            }
+            (parts[0].to_string(), &parts[1..])
+        } else if let Some(section) = pending_section.take()
+            && line.starts_with("  ")
+        {
+            (section, &parts[..])
+        } else {
+            continue;
+        };
+
+        if let [addr, size, object @ ..] = fields
+            && let Some(address) = parse_map_number(addr)
+            && let Some(size) = parse_map_number(size)
+            && size > 0
+            && !object.is_empty()
+        {
+            entries.push(MapEntry {
+                section,
+                address,
+                size,
+                object: object.join(" "),
+            });
+        }
+    }
+
+    entries
+}
+
+fn parse_map_number(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+/// --map-sort：把 map 中的输入段按指定方式排序后输出
+fn write_sorted_map(map_path: &Path, output_path: &Path, order: MapSort) -> Result<()> {
+    let map = std::fs::read_to_string(map_path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", map_path.display(), e))?;
+
+    let mut entries = parse_map_entries(&map);
+    match order {
+        MapSort::Size => entries.sort_by_key(|e| std::cmp::Reverse(e.size)),
+        MapSort::Name => entries.sort_by(|a, b| a.section.cmp(&b.section)),
+        MapSort::Addr => entries.sort_by_key(|e| e.address),
+    }
+
+    let mut output = format!(
+        "# Input sections from {} sorted by {}\n",
+        map_path.display(),
+        format!("{:?}", order).to_lowercase()
+    );
+    output.push_str(&format!(
+        "{:<12} {:>10}  {:<40} {}\n",
+        "# address", "size", "section", "object"
+    ));
+    for entry in &entries {
+        output.push_str(&format!(
+            "{:#010x} {:>10}  {:<40} {}\n",
+            entry.address, entry.size, entry.section, entry.object
+        ));
+    }
+    std::fs::write(output_path, output)?;
+
+    progress!(
+        "  {} Sorted linker map ({} sections): {}",
+        style("🗺️").cyan(),
+        entries.len(),
+        style(output_path.display()).dim()
+    );
+    Ok(())
+}
+
+/// 生成在 .bss 之后预留栈保护区的链接脚本片段
+///
+/// 片段通过 INSERT 追加到项目自己的链接脚本中，不需要修改 SDK 中的脚本。
+fn write_stack_guard_script(project_root: &Path) -> Result<PathBuf> {
+    let build_dir = project_root.join("build");
+    std::fs::create_dir_all(&build_dir)?;
+    let path = build_dir.join(STACK_GUARD_SCRIPT);
+    std::fs::write(
+        &path,
+        format!(
+            r#"/* Generated by cargo ecos build --stack-guard */
+SECTIONS
+{{
+    .ecos_stack_guard (NOLOAD) : ALIGN(16)
+    {{
+        {start} = .;
+        . += ECOS_STACK_GUARD_SIZE;
+        {end} = .;
+    }}
+}}
+INSERT AFTER .bss;
+"#,
+            start = STACK_GUARD_START_SYMBOL,
+            end = STACK_GUARD_END_SYMBOL
+        ),
+    )?;
+    Ok(path)
+}
+
+/// cargo 的 target 目录，相对路径以项目根目录为基准
+///
+/// 项目属于某个 workspace 时，target 目录位于 workspace 根目录。
+pub(crate) fn target_dir(project_root: &Path) -> PathBuf {
+    if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        return project_root.join(dir);
+    }
+
+    workspace_root(project_root)
+        .unwrap_or_else(|| project_root.to_path_buf())
+        .join("target")
+}
+
+/// --target-dir 相对路径按调用时的当前目录解析
+fn parse_target_dir(value: &str) -> std::result::Result<PathBuf, String> {
+    std::path::absolute(value).map_err(|e| format!("invalid target dir '{}': {}", value, e))
+}
+
+/// 向上查找包含 [workspace] 的 Cargo.toml
+fn workspace_root(project_root: &Path) -> Option<PathBuf> {
+    crate::cmd::find_workspace_root(project_root.parent()?)
+}
+
+/// 解析十六进制地址，如 0x30000000
+fn parse_hex_addr(value: &str) -> std::result::Result<u32, String> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+
+    u32::from_str_radix(digits, 16)
+        .map_err(|_| format!("'{}' is not a valid hex address (e.g. 0x30000000)", value))
+}
+
+/// 校验 --arch-flags，如 +m,+a,-c
+fn parse_arch_flags(value: &str) -> std::result::Result<String, String> {
+    let flags: Vec<&str> = value
+        .split(',')
+        .map(|flag| flag.trim())
+        .filter(|flag| !flag.is_empty())
+        .collect();
+
+    if flags.is_empty() {
+        return Err("expected at least one feature, e.g. +m,+a,-c".to_string());
+    }
+
+    for flag in &flags {
+        let feature = flag
+            .strip_prefix('+')
+            .or_else(|| flag.strip_prefix('-'))
+            .ok_or_else(|| format!("'{}' must start with '+' or '-'", flag))?;
+
+        if !RISCV_TARGET_FEATURES.contains(&feature) {
+            return Err(format!(
+                "unknown RISC-V feature '{}' (known: {})",
+                feature,
+                RISCV_TARGET_FEATURES.join(", ")
+            ));
+        }
+    }
+
+    Ok(flags.join(","))
+}
+
+/// 校验 --cfg：KEY 或 KEY=VALUE，KEY 必须是合法的 Rust 标识符；
+/// 返回 rustc 的写法 KEY 或 KEY="VALUE"
+fn parse_cfg(value: &str) -> std::result::Result<String, String> {
+    let (key, val) = match value.split_once('=') {
+        Some((key, val)) => (key.trim(), Some(val.trim().trim_matches('"'))),
+        None => (value.trim(), None),
+    };
+
+    let mut chars = key.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if !valid_start || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') || key == "_" {
+        return Err(format!("'{}' is not a valid Rust identifier", key));
+    }
+
+    // 值会放进 key="value" 中，不能包含引号
+    match val {
+        Some(val) if val.contains('"') => {
+            Err(format!("cfg value '{}' must not contain quotes", val))
+        }
+        Some(val) => Ok(format!("{}=\"{}\"", key, val)),
+        None => Ok(key.to_string()),
+    }
+}
+
+/// 将 verilog hex 中的 @地址 标记减去加载地址
+fn rebase_hex_markers(content: &str, load_addr: u32) -> String {
+    let mut output = String::with_capacity(content.len());
+
+    for line in content.lines() {
+        match line
+            .strip_prefix('@')
+            .and_then(|addr| u32::from_str_radix(addr.trim(), 16).ok())
+        {
+            Some(addr) if addr >= load_addr => {
+                output.push_str(&format!("@{:08X}", addr - load_addr));
+            }
+            _ => output.push_str(line),
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// 将 S-record 中的数据/起始地址减去加载地址，并重新计算校验和
+fn rebase_srec(content: &str, load_addr: u32) -> String {
+    let mut output = String::with_capacity(content.len());
+
+    for line in content.lines() {
+        match rebase_srec_record(line.trim_end(), load_addr) {
+            Some(record) => output.push_str(&record),
+            None => output.push_str(line),
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn rebase_srec_record(line: &str, load_addr: u32) -> Option<String> {
+    let record_type = line.get(0..2)?;
+    let addr_len = match record_type {
+        "S1" | "S9" => 2,
+        "S2" | "S8" => 3,
+        "S3" | "S7" => 4,
+        _ => return None,
+    };
+
+    // 字节布局：count, address..., data..., checksum
+    let hex = line.get(2..)?;
+    let mut bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if bytes.len() != bytes[0] as usize + 1 || bytes.len() < addr_len + 2 {
+        return None;
+    }
+
+    let addr = bytes[1..=addr_len]
+        .iter()
+        .fold(0u32, |acc, b| (acc << 8) | *b as u32);
+    if addr < load_addr {
+        return None;
+    }
+
+    let rebased = addr - load_addr;
+    for (i, byte) in bytes[1..=addr_len].iter_mut().enumerate() {
+        *byte = (rebased >> (8 * (addr_len - 1 - i))) as u8;
+    }
+
+    let checksum_index = bytes.len() - 1;
+    let sum = bytes[..checksum_index]
+        .iter()
+        .fold(0u8, |acc, b| acc.wrapping_add(*b));
+    bytes[checksum_index] = !sum;
+
+    let mut record = record_type.to_string();
+    for byte in &bytes {
+        record.push_str(&format!("{:02X}", byte));
+    }
+    Some(record)
+}
+
+/// 获取基础 RUSTFLAGS
+///
+/// 设置 CARGO_ENCODED_RUSTFLAGS 会覆盖 cargo 配置中的 rustflags，
+/// 因此需要先按 cargo 的优先级读取已有的配置，再追加额外参数。
+fn base_rustflags(project_root: &Path) -> Vec<String> {
+    if let Ok(flags) = std::env::var("CARGO_ENCODED_RUSTFLAGS") {
+        return flags
+            .split(RUSTFLAGS_SEPARATOR)
+            .filter(|f| !f.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Ok(flags) = std::env::var("RUSTFLAGS") {
+        return flags.split_whitespace().map(str::to_string).collect();
+    }
+
+    // 同一来源的数组按优先级从低到高拼接；target.<triple>.rustflags 存在时忽略 build.rustflags
+    let mut target_flags = Vec::new();
+    let mut build_flags = Vec::new();
+    for config in cargo_config_files(project_root) {
+        let Ok(content) = std::fs::read_to_string(&config) else {
+            continue;
+        };
+        let Ok(config) = toml::from_str::<toml::Value>(&content) else {
+            continue;
+        };
+        target_flags.extend(rustflags_value(
+            config
+                .get("target")
+                .and_then(|target| target.get(TARGET_TRIPLE))
+                .and_then(|target| target.get("rustflags")),
+        ));
+        build_flags.extend(rustflags_value(
+            config.get("build").and_then(|b| b.get("rustflags")),
+        ));
+    }
+
+    let target_env = format!(
+        "CARGO_TARGET_{}_RUSTFLAGS",
+        TARGET_TRIPLE.to_uppercase().replace('-', "_")
+    );
+    if let Ok(flags) = std::env::var(target_env) {
+        target_flags.extend(flags.split_whitespace().map(str::to_string));
+    }
+    if let Ok(flags) = std::env::var("CARGO_BUILD_RUSTFLAGS") {
+        build_flags.extend(flags.split_whitespace().map(str::to_string));
+    }
+
+    if target_flags.is_empty() {
+        build_flags
+    } else {
+        target_flags
+    }
+}
+
+/// cargo 会读取的配置文件，按优先级从低到高排列：
+/// $CARGO_HOME 中的配置，然后从文件系统根目录到项目目录逐级的 .cargo/config(.toml)
+fn cargo_config_files(project_root: &Path) -> Vec<PathBuf> {
+    let config_in = |dir: &Path| {
+        ["config.toml", "config"]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    };
+
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")));
+
+    let mut files: Vec<PathBuf> = cargo_home
+        .as_deref()
+        .and_then(config_in)
+        .into_iter()
+        .collect();
+    let mut ancestors: Vec<&Path> = project_root.ancestors().collect();
+    ancestors.reverse();
+    for dir in ancestors {
+        if let Some(config) = config_in(&dir.join(".cargo"))
+            && !files.contains(&config)
+        {
+            files.push(config);
+        }
+    }
+    files
+}
+
+/// rustflags 既可以是字符串数组，也可以是以空白分隔的字符串
+fn rustflags_value(value: Option<&toml::Value>) -> Vec<String> {
+    match value {
+        Some(toml::Value::String(flags)) => flags.split_whitespace().map(str::to_string).collect(),
+        Some(toml::Value::Array(flags)) => flags
+            .iter()
+            .filter_map(|f| f.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// --linker 对应的 RUSTFLAGS
+///
+/// 项目的 link-arg 都是 gcc 驱动参数（-mabi、-Wl,...），因此 mold/lld
+/// 仍通过 C 编译器驱动调用，只替换实际使用的链接器。
+fn linker_flags(linker: &str) -> Result<Vec<String>> {
+    let require = |tool: &str| {
+        which::which(tool).map_err(|_| {
+            anyhow::anyhow!(
+                "Linker '{}' not found in PATH (required by --linker {})",
+                tool,
+                linker
+            )
+        })
+    };
+
+    let flags: Vec<String> = match linker {
+        "mold" => {
+            require("mold")?;
+            vec!["link-arg=-fuse-ld=mold".to_string()]
+        }
+        "lld" | "ld.lld" => {
+            require("clang")?;
+            require("ld.lld")?;
+            vec![
+                "linker=clang".to_string(),
+                "link-arg=--target=riscv32-unknown-none-elf".to_string(),
+                "link-arg=-fuse-ld=lld".to_string(),
+            ]
+        }
+        _ => {
+            require(linker)?;
+            vec![format!("linker={}", linker)]
+        }
+    };
+
+    Ok(flags
+        .into_iter()
+        .flat_map(|flag| ["-C".to_string(), flag])
+        .collect())
+}
+
+/// run_cargo_counting_warnings 的结果
+struct CargoJsonRun {
+    status: std::process::ExitStatus,
+    /// lint 名称 -> 警告数
+    warnings: BTreeMap<String, usize>,
+    /// "crate vX.Y.Z" -> 启用的 features
+    features: BTreeMap<String, Vec<String>>,
+}
+
+/// 以 JSON 格式运行 cargo，转发诊断信息并按 lint 统计警告，
+/// 同时记录为目标平台编译的每个 crate 启用的 features
+///
+/// 在 -D warnings 下，lint 诊断的 level 会变为 error，
+/// 但其 code 仍是 lint 名称（而非 E0xxx），据此区分。
+fn run_cargo_counting_warnings(
+    cargo_cmd: &mut StdCommand,
+    max_build_time: Option<u64>,
+) -> Result<CargoJsonRun> {
+    let mut child = cargo_cmd
+        .stdout(Stdio::piped())
+        .stderr(child_stdio())
+        .spawn()?;
+    let watchdog = BuildWatchdog::start(&child, max_build_time);
+
+    let mut warnings: BTreeMap<String, usize> = BTreeMap::new();
+    let mut features: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    for line in BufReader::new(stdout).lines() {
+        let line = line?;
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+            progress!("{}", line);
+            continue;
+        };
+
+        if message["reason"] == "compiler-artifact" {
+            record_artifact_features(&message, &mut features);
+            continue;
+        }
+        if message["reason"] != "compiler-message" {
+            continue;
+        }
+
+        let diagnostic = &message["message"];
+        if let Some(rendered) = diagnostic["rendered"].as_str()
+            && !COMPACT_REPORT.load(Ordering::Relaxed)
+        {
+            eprint!("{}", rendered);
+        }
+
+        let level = diagnostic["level"].as_str().unwrap_or_default();
+        let code = diagnostic["code"]["code"].as_str();
+        let is_lint = match (level, code) {
+            ("warning", _) => true,
+            ("error", Some(code)) => !is_error_code(code),
+            _ => false,
+        };
+
+        if is_lint {
+            let category = code.unwrap_or("other").to_string();
+            *warnings.entry(category).or_default() += 1;
+        }
+    }
+
+    let status = child.wait()?;
+    watchdog.finish()?;
+    Ok(CargoJsonRun {
+        status,
+        warnings,
+        features,
+    })
+}
+
+/// 记录 compiler-artifact 消息中的 features；主机上编译的构建脚本、过程宏及其依赖
+/// 使用 std 是正常的，只保留输出在目标平台目录下的产物
+fn record_artifact_features(
+    message: &serde_json::Value,
+    features: &mut BTreeMap<String, Vec<String>>,
+) {
+    let for_target = message["filenames"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str())
+        .any(|f| f.contains(TARGET_TRIPLE));
+    if !for_target {
+        return;
+    }
+
+    let Some(name) = message["target"]["name"].as_str() else {
+        return;
+    };
+    let package_id = message["package_id"].as_str().unwrap_or_default();
+    let key = match package_id_version(package_id) {
+        Some(version) => format!("{} v{}", name, version),
+        None => name.to_string(),
+    };
+    let enabled = message["features"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str().map(str::to_string))
+        .collect();
+    features.insert(key, enabled);
+}
+
+/// 从 package_id 中取版本号，兼容 "registry+...#name@1.0.0"、"path+...#1.0.0"
+/// 与旧格式 "name 1.0.0 (source)"
+fn package_id_version(package_id: &str) -> Option<&str> {
+    match package_id.rsplit_once('#') {
+        Some((_, spec)) => Some(spec.rsplit_once('@').map_or(spec, |(_, version)| version)),
+        None => package_id.split_whitespace().nth(1),
+    }
+}
+
+/// --features-report：crate -> 启用的 features，std / alloc 标红
+fn print_features_report(features: &BTreeMap<String, Vec<String>>) {
+    progress!(
+        "{} Enabled features ({} crate(s) for {}):",
+        style("🧩").cyan(),
+        features.len(),
+        TARGET_TRIPLE
+    );
+
+    let width = features.keys().map(|name| name.len()).max().unwrap_or(0);
+    let mut flagged = 0;
+    for (name, enabled) in features {
+        let uses_std = enabled.iter().any(|f| f == "std" || f == "alloc");
+        if uses_std {
+            flagged += 1;
+        }
+
+        let list = if enabled.is_empty() {
+            style("-".to_string()).dim().to_string()
+        } else {
+            enabled
+                .iter()
+                .map(|f| match f.as_str() {
+                    "std" | "alloc" => style(f).red().bold().to_string(),
+                    _ => f.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let name = format!("{:<width$}", name, width = width);
+        if uses_std {
+            progress!("  {}  {}", style(name).red(), list);
+        } else {
+            progress!("  {}  {}", name, list);
+        }
+    }
+
+    if flagged > 0 {
+        progress!(
+            "{} {} crate(s) enable std or alloc (marked in red)",
+            style("⚠️").yellow(),
+            flagged
+        );
+    }
+}
+
+// 超时后发送 SIGTERM，再等待该时长仍未退出则 SIGKILL
+const BUILD_KILL_GRACE: Duration = Duration::from_secs(5);
+
+/// --max-build-time 看门狗线程，超时后终止 cargo 进程
+struct BuildWatchdog {
+    limit: Option<u64>,
+    done: Option<mpsc::Sender<()>>,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl BuildWatchdog {
+    fn start(child: &std::process::Child, limit: Option<u64>) -> Self {
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let Some(secs) = limit else {
+            return Self {
+                limit,
+                done: None,
+                timed_out,
+            };
+        };
+
+        let (done, finished) = mpsc::channel::<()>();
+        let pid = child.id();
+        let flag = Arc::clone(&timed_out);
+        std::thread::spawn(move || {
+            // 主线程 wait 结束后 drop Sender，recv 返回 Disconnected
+            if finished.recv_timeout(Duration::from_secs(secs))
+                != Err(mpsc::RecvTimeoutError::Timeout)
+            {
+                return;
+            }
+            flag.store(true, Ordering::SeqCst);
+            terminate_process(pid, false);
+            if finished.recv_timeout(BUILD_KILL_GRACE) == Err(mpsc::RecvTimeoutError::Timeout) {
+                terminate_process(pid, true);
+            }
+        });
+
+        Self {
+            limit,
+            done: Some(done),
+            timed_out,
+        }
+    }
+
+    /// 子进程已退出后调用，若是被看门狗终止则返回超时错误
+    fn finish(mut self) -> Result<()> {
+        self.done.take();
+        if self.timed_out.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!(
+                "Build timed out after {}s (--max-build-time), cargo was terminated",
+                self.limit.unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn terminate_process(pid: u32, force: bool) {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    unsafe {
+        libc::kill(pid as libc::pid_t, signal);
+    }
+}
+
+#[cfg(not(unix))]
+fn terminate_process(pid: u32, force: bool) {
+    let mut cmd = StdCommand::new("taskkill");
+    cmd.args(["/PID", &pid.to_string(), "/T"]);
+    if force {
+        cmd.arg("/F");
+    }
+    let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+/// rustc 的硬错误代码形如 E0308
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// 按类别输出警告数量
+fn print_warning_summary(warnings: &BTreeMap<String, usize>) {
+    let total: usize = warnings.values().sum();
+    progress!(
+        "\n{} {} warning(s) by category:",
+        style("⚠️").yellow(),
+        total
+    );
+
+    let mut sorted: Vec<_> = warnings.iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    for (category, count) in sorted {
+        progress!("  {:>4}  {}", count, category);
+    }
+}
+
+/// 缓存的 no_std 列表位置
+fn known_nostd_cache() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cargo-ecos").join("known_nostd.json"))
+}
+
+/// 读取 no_std 兼容列表，优先使用缓存
+fn load_known_nostd() -> Result<Vec<String>> {
+    let content = known_nostd_cache()
+        .and_then(|cache| std::fs::read_to_string(cache).ok())
+        .unwrap_or_else(|| KNOWN_NOSTD_JSON.to_string());
+
+    let known: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid known no_std list: {}", e))?;
+
+    Ok(known["crates"]
+        .as_array()
+        .map(|crates| {
+            crates
+                .iter()
+                .filter_map(|c| c.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// 重新下载 no_std 兼容列表到缓存
+fn update_known_nostd(project_root: &Path) -> Result<()> {
+    let url = crate::cmd::ecos_metadata_str(project_root, "ecos_known_nostd_url")
+        .unwrap_or_else(|| KNOWN_NOSTD_URL.to_string());
+    let cache =
+        known_nostd_cache().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
+    if let Some(parent) = cache.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    progress!(
+        "{} Updating known no_std list from {}",
+        style("🌐").cyan(),
+        style(&url).dim()
+    );
+
+    let output = crate::cmd::curl_command()?
+        .args(["-fsSL", &url])
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run curl: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to download known no_std list: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    // 校验下载内容后再写入缓存
+    serde_json::from_slice::<serde_json::Value>(&output.stdout)
+        .map_err(|e| anyhow::anyhow!("Downloaded list is not valid JSON: {}", e))?;
+    std::fs::write(&cache, &output.stdout)?;
+
+    progress!("  Saved to {}", style(cache.display()).dim());
+    Ok(())
+}
+
+/// 检查 Cargo.lock 中的 crate 是否都在 no_std 兼容列表中
+fn check_nostd_deps(project_root: &Path, project_name: &str) -> Result<()> {
+    // 工作区成员的 Cargo.lock 位于工作区根目录
+    let lock_path = workspace_root(project_root)
+        .unwrap_or_else(|| project_root.to_path_buf())
+        .join("Cargo.lock");
+    if !lock_path.exists() {
+        progress!(
+            "{} Cargo.lock not found, run 'cargo generate-lockfile' to check dependencies",
+            style("⚠️").yellow()
+        );
+        return Ok(());
+    }
+
+    progress!(
+        "{} Checking dependencies for no_std compatibility...",
+        style("🔍").cyan()
+    );
+
+    let lock: toml::Value = toml::from_str(&std::fs::read_to_string(&lock_path)?)?;
+    let known = load_known_nostd()?;
+
+    let mut unknown: Vec<String> = lock
+        .get("package")
+        .and_then(|p| p.as_array())
+        .map(|packages| {
+            packages
+                .iter()
+                // 跳过项目自身以及本地路径依赖（没有 source）
+                .filter(|p| p.get("source").is_some())
+                .filter_map(|p| p.get("name").and_then(|n| n.as_str()))
+                .filter(|name| *name != project_name)
+                .filter(|name| {
+                    !known
+                        .iter()
+                        .any(|k| k.replace('_', "-") == name.replace('_', "-"))
+                })
+                .map(|name| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    unknown.sort();
+    unknown.dedup();
+
+    if unknown.is_empty() {
+        progress!(
+            "  {} All dependencies are known no_std crates",
+            style("✓").green()
+        );
+    } else {
+        progress!(
+            "{} {} crate(s) not in the known no_std list:",
+            style("⚠️").yellow(),
+            unknown.len()
+        );
+        for name in &unknown {
+            progress!("  - {}", style(name).yellow());
+        }
+        progress!("  These may pull in std and cause link failures.");
+    }
+
+    Ok(())
+}
+
+/// 通过 rustc -vV 获取主机 target
+fn host_triple() -> Result<String> {
+    let output = StdCommand::new("rustc").arg("-vV").output()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host:"))
+        .map(|host| host.trim().to_string())
+        .ok_or_else(|| anyhow::anyhow!("Could not determine host target from 'rustc -vV'"))
+}
+
+/// 工作区是否有未提交的修改
+pub(crate) fn git_tree_dirty(project_root: &Path) -> bool {
+    StdCommand::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .current_dir(project_root)
+        .output()
+        .map(|output| output.status.success() && !output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// 若不存在则生成 src/version.rs，由 ECOS_GIT_HASH 提供 GIT_HASH
+fn ensure_version_rs(project_root: &Path) -> Result<()> {
+    let version_rs = project_root.join("src").join("version.rs");
+    if version_rs.exists() {
+        return Ok(());
+    }
+
+    std::fs::write(
+        &version_rs,
+        "// Generated by cargo ecos build --embed-git-hash\n\
+         pub const GIT_HASH: &str = env!(\"ECOS_GIT_HASH\");\n",
+    )?;
+    progress!(
+        "  📄 Created: {} (add `mod version;` to use version::GIT_HASH)",
+        style(version_rs.display()).dim()
+    );
+    Ok(())
+}
+
+/// 获取当前 git 提交的短哈希
+fn git_short_hash(project_root: &Path) -> Option<String> {
+    let output = StdCommand::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(project_root)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!hash.is_empty()).then_some(hash)
+}
+
+/// 检查 rust-toolchain(.toml) 是否指定了 nightly
+fn toolchain_file_is_nightly(project_root: &Path) -> bool {
+    toolchain_file_channel(project_root)
+        .map(|channel| channel.starts_with("nightly"))
+        .unwrap_or(false)
+}
+
+/// rust-toolchain.toml / rust-toolchain 中指定的 channel
+fn toolchain_file_channel(project_root: &Path) -> Option<String> {
+    if let Ok(content) = std::fs::read_to_string(project_root.join("rust-toolchain.toml")) {
+        return toml::from_str::<toml::Value>(&content)
+            .ok()?
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()
+            .map(|c| c.to_string());
+    }
+
+    // 旧格式：文件内容只有 channel 名称
+    std::fs::read_to_string(project_root.join("rust-toolchain"))
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|channel| !channel.is_empty())
+}
+
+/// --no-rustup-check：确定本次构建使用的工具链，未安装时报错而不是让 rustup 自动安装
+fn resolve_installed_toolchain(project_root: &Path, nightly: bool) -> Result<String> {
+    let toolchain = match toolchain_file_channel(project_root) {
+        Some(channel) => channel,
+        None if nightly => "nightly".to_string(),
+        None => {
+            let output = StdCommand::new("rustup")
+                .args(["show", "active-toolchain"])
+                .env("RUSTUP_AUTO_INSTALL", "0")
+                .current_dir(project_root)
+                .output()
+                .map_err(|_| {
+                    anyhow::anyhow!("rustup not found, --no-rustup-check requires rustup")
+                })?;
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .next()
+                .map(|name| name.to_string())
+                .ok_or_else(|| anyhow::anyhow!("Could not determine the active Rust toolchain"))?
+        }
+    };
+
+    let output = StdCommand::new("rustup")
+        .args(["toolchain", "list"])
+        .output()
+        .map_err(|_| anyhow::anyhow!("rustup not found, --no-rustup-check requires rustup"))?;
+    let installed = String::from_utf8_lossy(&output.stdout);
+    if !installed.lines().any(|line| line.starts_with(&toolchain)) {
+        return Err(anyhow::anyhow!(
+            "Rust toolchain '{}' is not installed.\n\
+             Run: rustup toolchain install {}",
+            toolchain,
+            toolchain
+        ));
+    }
+
+    Ok(toolchain)
+}
+
+/// 检查 nightly 工具链是否已安装
+fn check_nightly_installed() -> Result<()> {
+    let output = StdCommand::new("rustup")
+        .args(["toolchain", "list"])
+        .output()
+        .map_err(|_| anyhow::anyhow!("rustup not found, --nightly requires rustup"))?;
+
+    let toolchains = String::from_utf8_lossy(&output.stdout);
+    if !toolchains.lines().any(|line| line.starts_with("nightly")) {
+        return Err(anyhow::anyhow!(
+            "Nightly toolchain is not installed.\n\
+             Run: rustup toolchain install nightly"
+        ));
+    }
+
+    Ok(())
+}
+
+/// 检查 Cargo.toml 的 [profile.*] 是否已经设置 panic = "abort"
+fn profile_sets_panic_abort(project_root: &Path) -> Result<bool> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml"))?;
+    let cargo_toml: toml::Value = toml::from_str(&content)?;
+
+    Ok(cargo_toml
+        .get("profile")
+        .and_then(|profiles| profiles.as_table())
+        .map(|profiles| {
+            profiles
+                .values()
+                .any(|profile| profile.get("panic").and_then(|p| p.as_str()) == Some("abort"))
+        })
+        .unwrap_or(false))
+}
+
+/// Cargo.toml 中的 [profile.<profile>] 是否开启了 LTO
+///
+/// profile 只在 workspace 根目录的 Cargo.toml 中生效。
+fn profile_enables_lto(project_root: &Path, profile: &str) -> Result<bool> {
+    let root = workspace_root(project_root).unwrap_or_else(|| project_root.to_path_buf());
+    let content = std::fs::read_to_string(root.join("Cargo.toml"))?;
+    let cargo_toml: toml::Value = toml::from_str(&content)?;
+
+    Ok(
+        match cargo_toml
+            .get("profile")
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(|profile| profile.get("lto"))
+        {
+            Some(toml::Value::Boolean(lto)) => *lto,
+            Some(toml::Value::String(lto)) => !matches!(lto.as_str(), "off" | "false"),
+            _ => false,
+        },
+    )
+}
+
+/// --size-trend：用 sparkline 显示最近几次构建的 .bin 大小
+fn print_size_trend(project_root: &Path) -> Result<()> {
+    let history = project_root.join("build").join(SIZE_HISTORY_FILE);
+    let content = std::fs::read_to_string(&history).map_err(|_| {
+        anyhow::anyhow!(
+            "No size history at {}.\nRun 'cargo ecos build' (without --no-size-history) first.",
+            history.display()
+        )
+    })?;
+
+    // timestamp,git_commit,profile,bin_size_bytes,hex_size_bytes
+    let rows: Vec<(String, String, String, u64)> = content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let size = fields.get(3)?.parse().ok()?;
+            Some((
+                fields[0].to_string(),
+                fields[1].to_string(),
+                fields[2].to_string(),
+                size,
+            ))
+        })
+        .collect();
+    let rows = &rows[rows.len().saturating_sub(SIZE_TREND_ROWS)..];
+    if rows.is_empty() {
+        println!("{} No builds recorded yet", style("ℹ️").cyan());
+        return Ok(());
+    }
+
+    let sizes: Vec<u64> = rows.iter().map(|row| row.3).collect();
+    let min = *sizes.iter().min().unwrap_or(&0);
+    let max = *sizes.iter().max().unwrap_or(&0);
+    let sparkline: String = sizes
+        .iter()
+        .map(|size| {
+            let level = if max == min {
+                0
+            } else {
+                ((size - min) * (SPARK_CHARS.len() as u64 - 1) / (max - min)) as usize
+            };
+            SPARK_CHARS[level]
+        })
+        .collect();
+
+    println!(
+        "{} Binary size trend (last {} builds): {}",
+        style("📈").cyan(),
+        rows.len(),
+        style(sparkline).cyan()
+    );
+    for (timestamp, commit, profile, size) in rows {
+        println!(
+            "  {}  {:<9} {:<7} {}",
+            style(timestamp).dim(),
+            if commit.is_empty() { "-" } else { commit },
+            profile,
+            format_size(*size, DECIMAL)
+        );
+    }
+    println!(
+        "  min {}, max {}",
+        style(format_size(min, DECIMAL)).green(),
+        style(format_size(max, DECIMAL)).yellow()
+    );
+
+    Ok(())
+}
+
+/// 打印与上一次构建相比的体积变化
+fn print_size_change(previous: u64, current: u64) {
+    if current < previous {
+        progress!(
+            "{} Binary size: {} -> {} (saved {})",
+            style("📉").cyan(),
+            format_size(previous, DECIMAL),
+            format_size(current, DECIMAL),
+            style(format_size(previous - current, DECIMAL)).green()
+        );
+    } else {
+        progress!(
+            "{} Binary size: {} -> {} (+{})",
+            style("📈").cyan(),
+            format_size(previous, DECIMAL),
+            format_size(current, DECIMAL),
+            format_size(current - previous, DECIMAL)
+        );
+    }
+}
+
+/// 打印目标平台的依赖树，启用了 std feature 的 crate 标红
+fn print_dependency_tree(project_root: &Path) -> Result<()> {
+    // 只看 normal 依赖：build 依赖和过程宏运行在主机上，使用 std 是正常的
+    let output = StdCommand::new("cargo")
+        .args([
+            "tree",
+            "--target",
+            TARGET_TRIPLE,
+            "-e",
+            "normal",
+            "--format",
+            "{p}:{f}",
+        ])
+        .current_dir(project_root)
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("cargo tree failed"));
+    }
+
+    let mut std_crates = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (package, features) = line.rsplit_once(':').unwrap_or((line, ""));
+        let uses_std = features.split(',').any(|f| f.trim() == "std");
+
+        if uses_std {
+            std_crates += 1;
+            println!("{} {}", style(package).red(), style("[std]").red().bold());
+        } else if features.is_empty() {
+            println!("{}", package);
+        } else {
+            println!("{} {}", package, style(format!("[{}]", features)).dim());
+        }
+    }
+
+    println!();
+    if std_crates == 0 {
+        println!(
+            "{} No dependency has the std feature enabled",
+            style("✓").green()
+        );
+    } else {
+        println!(
+            "{} {} crate(s) have the std feature enabled (marked in red)",
+            style("⚠️").yellow(),
+            std_crates
+        );
+    }
+
+    Ok(())
+}
+
+/// nm 输出中的一个符号
+struct Symbol {
+    address: u64,
+    size: u64,
+    kind: char,
+    name: String,
+}
+
+impl Symbol {
+    /// 解析 `nm --print-size` 的一行：地址 大小 类型 名称
+    fn parse(line: &str) -> Option<Symbol> {
+        let mut parts = line.split_whitespace();
+        let address = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let size = u64::from_str_radix(parts.next()?, 16).ok()?;
+        let kind = parts.next()?.chars().next()?;
+        let name = parts.collect::<Vec<_>>().join(" ");
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Symbol {
+            address,
+            size,
+            kind,
+            name: rustc_demangle::demangle(&name).to_string(),
+        })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "address": self.address,
+            "size": self.size,
+            "kind": self.kind.to_string(),
+            "name": self.name,
+        })
+    }
+}
+
+/// 输出 SDK 写入 .ecos_resources 段的硬件资源占用（JSON），没有该段时静默跳过
+fn report_resources_section(elf_path: &Path) {
+    use object::{Object, ObjectSection};
+
+    let Ok(data) = std::fs::read(elf_path) else {
+        return;
+    };
+    let Ok(elf) = object::File::parse(&*data) else {
+        return;
+    };
+    let Some(section) = elf.section_by_name(RESOURCES_SECTION) else {
+        return;
+    };
+    let Ok(raw) = section.data() else {
+        return;
+    };
+
+    // 段按对齐补零
+    let text = String::from_utf8_lossy(raw);
+    let text = text.trim_end_matches('\0').trim();
+    let resources = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => {
+            progress!(
+                "{} {} is not a JSON object (CBOR is not supported), skipped",
+                style("⚠️").yellow(),
+                RESOURCES_SECTION
+            );
+            return;
+        }
+    };
+
+    progress!(
+        "{} Hardware resources ({}):",
+        style("🔌").cyan(),
+        RESOURCES_SECTION
+    );
+    let width = resources.keys().map(|k| k.len()).max().unwrap_or(0);
+    for (name, value) in &resources {
+        let value = match value {
+            serde_json::Value::Array(items) if items.is_empty() => "-".to_string(),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        progress!("  {:<width$}  {}", name, style(value).cyan(), width = width);
+    }
+}
+
+/// --max-stack-depth 的调用图：函数地址 -> (名称, 栈帧大小, 被调函数, 是否有间接调用)
+#[derive(Debug, Default)]
+struct StackFunction {
+    name: String,
+    frame: Option<u64>,
+    callees: Vec<u64>,
+    indirect_calls: bool,
+}
+
+/// --max-stack-depth：由 .stack_sizes 段（Rust）和 .su 文件（C）得到各函数栈帧，
+/// 结合 objdump 反汇编中的调用关系求最坏情况下的栈深度
+fn check_stack_depth(elf_path: &Path, su_dir: &Path, limit: u64) -> Result<()> {
+    progress!("{} Analysing worst-case stack depth...", style("📏").cyan());
+
+    let mut functions = disassembly_call_graph(elf_path)?;
+    let stack_sizes = read_stack_sizes(elf_path)?;
+    if stack_sizes.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No stack usage data in {} (the {} section is missing)",
+            elf_path.display(),
+            STACK_SIZES_SECTION
+        ));
+    }
+    let su_sizes = read_su_files(su_dir);
+    for (addr, function) in functions.iter_mut() {
+        function.frame = stack_sizes
+            .get(addr)
+            .or_else(|| su_sizes.get(&function.name))
+            .copied();
+    }
+
+    let Some((depth, path, mut recursive)) = worst_stack_path(&functions) else {
+        return Err(anyhow::anyhow!(
+            "No functions found in {}",
+            elf_path.display()
+        ));
+    };
+    let chain: Vec<String> = path
+        .iter()
+        .map(|addr| {
+            let function = &functions[addr];
+            format!(
+                "{} ({})",
+                function.name,
+                function.frame.map_or("?".to_string(), |f| f.to_string())
+            )
+        })
+        .collect();
+
+    let indirect: Vec<&str> = functions
+        .values()
+        .filter(|f| f.indirect_calls)
+        .map(|f| f.name.as_str())
+        .collect();
+    if !indirect.is_empty() {
+        progress!(
+            "{} Stack usage is incomplete: {} function(s) make indirect calls whose targets are not counted",
+            style("⚠️").yellow(),
+            indirect.len()
+        );
+        progress!("  {}", style(summarize_names(&indirect)).dim());
+    }
+    let unknown: Vec<&str> = functions
+        .values()
+        .filter(|f| f.frame.is_none())
+        .map(|f| f.name.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        progress!(
+            "{} No stack size for {} function(s) (assembly, or C built without -fstack-usage), counted as 0",
+            style("⚠️").yellow(),
+            unknown.len()
+        );
+        progress!("  {}", style(summarize_names(&unknown)).dim());
+    }
+    if !recursive.is_empty() {
+        recursive.sort();
+        recursive.dedup();
+        let names: Vec<&str> = recursive
+            .iter()
+            .map(|addr| functions[addr].name.as_str())
+            .collect();
+        progress!(
+            "{} Recursion found, counted only once: {}",
+            style("⚠️").yellow(),
+            style(summarize_names(&names)).dim()
+        );
+    }
+
+    let message = format!(
+        "Worst-case stack depth: {} bytes (limit {} bytes)",
+        depth, limit
+    );
+    if depth > limit {
+        progress!("{} {}", style("❌").red(), message);
+        progress!("  {}", chain.join(" -> "));
+        return Err(anyhow::anyhow!(
+            "Worst-case stack depth {} bytes exceeds --max-stack-depth {} bytes",
+            depth,
+            limit
+        ));
+    }
+    progress!("{} {}", style("✅").green(), message);
+    progress!("  {}", style(chain.join(" -> ")).dim());
+    Ok(())
+}
+
+/// 最坏情况下的栈深度，返回 (深度, 调用链上的函数地址, 递归调用的函数)
+///
+/// 从没有调用者的函数（入口、中断处理函数等）出发；所有函数都在环中时从每个函数出发。
+fn worst_stack_path(functions: &BTreeMap<u64, StackFunction>) -> Option<(u64, Vec<u64>, Vec<u64>)> {
+    let called: std::collections::HashSet<u64> = functions
+        .values()
+        .flat_map(|f| f.callees.iter().copied())
+        .collect();
+    let mut roots: Vec<u64> = functions
+        .keys()
+        .copied()
+        .filter(|addr| !called.contains(addr))
+        .collect();
+    if roots.is_empty() {
+        roots = functions.keys().copied().collect();
+    }
+
+    let mut memo = BTreeMap::new();
+    let mut recursive = Vec::new();
+    let (depth, root) = roots
+        .into_iter()
+        .map(|addr| {
+            let mut on_path = Vec::new();
+            let depth = stack_depth(addr, functions, &mut memo, &mut on_path, &mut recursive);
+            (depth, addr)
+        })
+        .max()?;
+
+    // 沿着记录的最深被调函数还原调用链
+    let mut path = Vec::new();
+    let mut current = Some(root);
+    while let Some(addr) = current
+        && !path.contains(&addr)
+    {
+        path.push(addr);
+        current = memo.get(&addr).and_then(|(_, next)| *next);
+    }
+
+    Some((depth, path, recursive))
+}
+
+/// 从 addr 出发的最大栈深度；memo 记录 (深度, 最深的被调函数)
+fn stack_depth(
+    addr: u64,
+    functions: &BTreeMap<u64, StackFunction>,
+    memo: &mut BTreeMap<u64, (u64, Option<u64>)>,
+    on_path: &mut Vec<u64>,
+    recursive: &mut Vec<u64>,
+) -> u64 {
+    if let Some((depth, _)) = memo.get(&addr) {
+        return *depth;
+    }
+    let Some(function) = functions.get(&addr) else {
+        return 0;
+    };
+
+    on_path.push(addr);
+    let mut deepest = (0, None);
+    for &callee in &function.callees {
+        if on_path.contains(&callee) {
+            recursive.push(callee);
+            continue;
+        }
+        let depth = stack_depth(callee, functions, memo, on_path, recursive);
+        if depth > deepest.0 || deepest.1.is_none() {
+            deepest = (depth, Some(callee));
+        }
+    }
+    on_path.pop();
+
+    let depth = function.frame.unwrap_or(0) + deepest.0;
+    memo.insert(addr, (depth, deepest.1));
+    depth
+}
+
+/// 只列出前几个函数名
+fn summarize_names(names: &[&str]) -> String {
+    const SHOWN: usize = 5;
+    let mut text = names
+        .iter()
+        .take(SHOWN)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        text.push_str(&format!(", ... ({} more)", names.len() - SHOWN));
+    }
+    text
+}
+
+/// 解析 objdump -d 的输出，得到每个函数的直接调用与是否存在间接调用
+fn disassembly_call_graph(elf_path: &Path) -> Result<BTreeMap<u64, StackFunction>> {
+    let output = StdCommand::new("riscv64-unknown-elf-objdump")
+        .arg("-d")
+        .arg(elf_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "riscv64-unknown-elf-objdump failed on {}",
+            elf_path.display()
+        ));
+    }
+
+    Ok(parse_call_graph(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// 从反汇编文本中提取函数与调用关系
+fn parse_call_graph(disassembly: &str) -> BTreeMap<u64, StackFunction> {
+    let mut functions: BTreeMap<u64, StackFunction> = BTreeMap::new();
+    let mut calls = Vec::new();
+    let mut current = None;
+
+    for line in disassembly.lines() {
+        // 函数头，如 "80000010 <main>:"
+        if let Some((addr, rest)) = line.split_once(" <")
+            && let Some(name) = rest.strip_suffix(">:")
+            && let Ok(addr) = u64::from_str_radix(addr.trim(), 16)
+        {
+            functions.insert(
+                addr,
+                StackFunction {
+                    name: format!("{:#}", rustc_demangle::demangle(name)),
+                    ..Default::default()
+                },
+            );
+            current = Some(addr);
+            continue;
+        }
+
+        // 指令行："80000014:\t00c000ef          \tjal\t80000020 <foo>"
+        let Some(caller) = current else {
+            continue;
+        };
+        let mut fields = line.split('\t').skip(2);
+        let (Some(mnemonic), operands) = (fields.next(), fields.next().unwrap_or_default()) else {
+            continue;
+        };
+        let mnemonic = mnemonic.trim().trim_start_matches("c.");
+        // auipc + jalr 组合由 objdump 在注释中给出目标："jalr\t-4(ra) # 80000020 <foo>"
+        let target = operands
+            .contains('<')
+            .then_some(operands)
+            .map(|operands| {
+                operands
+                    .rsplit_once(['#', ','])
+                    .map_or(operands, |(_, t)| t)
+            })
+            .and_then(|target| target.split_whitespace().next())
+            .and_then(|addr| u64::from_str_radix(addr, 16).ok());
+
+        match mnemonic {
+            "jal" | "call" | "tail" | "j" | "jalr" if let Some(target) = target => {
+                calls.push((caller, target));
+            }
+            // 返回（jr ra / ret）与函数内跳转表（jr）不是调用
+            "jalr" if !operands.starts_with("zero") => {
+                if let Some(function) = functions.get_mut(&caller) {
+                    function.indirect_calls = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 只保留跳到函数入口的调用，函数内部的 j / jal 跳转忽略
+    for (caller, target) in calls {
+        if target != caller
+            && functions.contains_key(&target)
+            && let Some(function) = functions.get_mut(&caller)
+            && !function.callees.contains(&target)
+        {
+            function.callees.push(target);
+        }
+    }
+
+    functions
+}
+
+/// 读取 .stack_sizes 段：每项为函数地址（目标指针宽度）+ ULEB128 编码的栈帧大小
+fn read_stack_sizes(elf_path: &Path) -> Result<BTreeMap<u64, u64>> {
+    use object::{Object, ObjectSection};
+
+    let data = std::fs::read(elf_path)?;
+    let elf = object::File::parse(&*data)?;
+    let pointer_size = if elf.is_64() { 8 } else { 4 };
+
+    let mut sizes = BTreeMap::new();
+    for section in elf
+        .sections()
+        .filter(|s| s.name() == Ok(STACK_SIZES_SECTION))
+    {
+        sizes.extend(decode_stack_sizes(section.data()?, pointer_size));
+    }
+
+    Ok(sizes)
+}
+
+/// 解码 .stack_sizes 段的内容，返回 (函数地址, 栈帧大小)
+fn decode_stack_sizes(raw: &[u8], pointer_size: usize) -> Vec<(u64, u64)> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + pointer_size <= raw.len() {
+        let mut addr_bytes = [0u8; 8];
+        addr_bytes[..pointer_size].copy_from_slice(&raw[offset..offset + pointer_size]);
+        offset += pointer_size;
+
+        let mut size = 0u64;
+        let mut shift = 0;
+        while let Some(&byte) = raw.get(offset) {
+            offset += 1;
+            size |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        entries.push((u64::from_le_bytes(addr_bytes), size));
+    }
+    entries
+}
+
+/// 读取 gcc -fstack-usage 生成的 .su 文件："file.c:12:6:func\t32\tstatic"
+fn read_su_files(dir: &Path) -> BTreeMap<String, u64> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "su"))
+        .filter_map(|entry| std::fs::read_to_string(entry.path()).ok())
+        .flat_map(|content| parse_su(&content))
+        .collect()
+}
+
+/// 解析 .su 文件的内容，返回 (函数名, 栈帧大小)
+fn parse_su(content: &str) -> Vec<(String, u64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next()?.rsplit(':').next()?.to_string();
+            let size = fields.next()?.trim().parse().ok()?;
+            Some((name, size))
+        })
+        .collect()
+}
+
+/// --symbols-json：用 nm 导出符号表并写入 JSON
+fn write_symbols_json(elf_path: &Path, output_path: &Path, filter: Option<&str>) -> Result<()> {
+    let filter = filter
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Invalid --symbols-json-filter: {}", e))?;
+
+    let output = StdCommand::new("riscv64-unknown-elf-nm")
+        .args(["--print-size", "--size-sort"])
+        .arg(elf_path)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "riscv64-unknown-elf-nm failed on {}",
+            elf_path.display()
+        ));
+    }
+
+    let symbols: Vec<Symbol> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(Symbol::parse)
+        .filter(|sym| filter.as_ref().is_none_or(|re| re.is_match(&sym.name)))
+        .collect();
+
+    let json: Vec<serde_json::Value> = symbols.iter().map(Symbol::to_json).collect();
+    std::fs::write(output_path, serde_json::to_string_pretty(&json)?)?;
+
+    progress!(
+        "  {} Wrote {} symbol(s) to {}",
+        style("🔣").cyan(),
+        symbols.len(),
+        style(output_path.display()).dim()
+    );
+    Ok(())
+}
+
+/// 读取 Cargo.toml [features] 中的 feature 名称（不含 default）
+fn cargo_features(project_root: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml"))?;
+    let cargo_toml: toml::Value = toml::from_str(&content)?;
+
+    Ok(cargo_toml
+        .get("features")
+        .and_then(|f| f.as_table())
+        .map(|table| {
+            table
+                .keys()
+                .filter(|name| *name != "default")
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// 读取 Cargo.toml 中的 package.version
+fn package_version(project_root: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml")).ok()?;
+    let cargo_toml: toml::Value = toml::from_str(&content).ok()?;
+
+    cargo_toml
+        .get("package")?
+        .get("version")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+fn check_environment() -> Result<()> {
+    // 检查 RISC-V 工具链
+    for tool in crate::cmd::doctor::TOOLCHAIN_TOOLS {
+        if which::which(tool).is_err() {
             return Err(anyhow::anyhow!(
                 "Tool '{}' not found in PATH.\n\
                  Please install RISC-V toolchain.",
@@ -331,3 +3340,197 @@ fn check_environment() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// S-record 校验和：count/地址/数据字节之和的反码
+    fn srec_checksum_ok(record: &str) -> bool {
+        let bytes: Vec<u8> = (2..record.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&record[i..i + 2], 16).unwrap())
+            .collect();
+        bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b)) == 0xFF
+    }
+
+    #[test]
+    fn map_entries_handle_wrapped_section_names() {
+        let map = "Linker script and memory map\n\
+                   \n\
+                   .text           0x30000000      0x130\n \
+                   *(.text.start)\n \
+                   .text.start    0x30000000       0x30 start.o\n \
+                   .text._ZN4demo4main17h0123456789abcdefE\n                \
+                   0x30000030      0x100 libdemo.rlib(demo.o)\n                \
+                   0x30000030                main\n";
+
+        let entries = parse_map_entries(map);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].section, ".text.start");
+        assert_eq!(entries[0].size, 0x30);
+        assert_eq!(
+            entries[1],
+            MapEntry {
+                section: ".text._ZN4demo4main17h0123456789abcdefE".to_string(),
+                address: 0x3000_0030,
+                size: 0x100,
+                object: "libdemo.rlib(demo.o)".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rebased_srec_keeps_header_and_fixes_addresses() {
+        let header = "S00F000068656C6C6F202020202000003C";
+        let data = "S30930000010DEADBEEF7E";
+        let start = "S70530000000CA";
+        let input = format!("{}\n{}\n{}\n", header, data, start);
+
+        let output = rebase_srec(&input, DEFAULT_LOAD_ADDR);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(output.starts_with("S0"));
+        assert_eq!(lines[0], header);
+        assert!(lines[1].starts_with("S30900000010DEADBEEF"));
+        assert!(lines[2].starts_with("S70500000000"));
+        assert!(lines[1..].iter().all(|line| srec_checksum_ok(line)));
+    }
+
+    #[test]
+    fn srec_below_load_address_is_untouched() {
+        let record = "S30910000000DEADBEEFAE";
+        assert_eq!(rebase_srec_record(record, DEFAULT_LOAD_ADDR), None);
+    }
+
+    #[test]
+    fn call_graph_from_objdump_output() {
+        let disassembly = "\
+80000000 <_start>:
+80000000:\t00000097          \tauipc\tra,0x0
+80000004:\t010080e7          \tjalr\t16(ra) # 80000010 <_ZN4demo4main17h0123456789abcdefE>
+
+80000010 <_ZN4demo4main17h0123456789abcdefE>:
+80000010:\t1141                \tc.addi\tsp,-16
+80000012:\t2039                \tjal\t80000020 <foo>
+80000014:\t2031                \tjal\t80000020 <foo>
+80000016:\t9782                \tjalr\ta5
+80000018:\ta001                \tj\t80000018 <_ZN4demo4main17h0123456789abcdefE+0x8>
+8000001a:\t8082                \tret
+
+80000020 <foo>:
+80000020:\t8082                \tjalr\tzero,0(ra)
+80000022:\ta009                \tj\t80000010 <_ZN4demo4main17h0123456789abcdefE>
+";
+        let functions = parse_call_graph(disassembly);
+        assert_eq!(functions.len(), 3);
+
+        let start = &functions[&0x8000_0000];
+        assert_eq!(start.callees, [0x8000_0010]);
+        assert!(!start.indirect_calls);
+
+        // 重复调用只记一次，函数内部的跳转不算调用
+        let main = &functions[&0x8000_0010];
+        assert_eq!(main.name, "demo::main");
+        assert_eq!(main.callees, [0x8000_0020]);
+        assert!(main.indirect_calls);
+
+        // 尾调用算作调用，jalr zero 是返回
+        let foo = &functions[&0x8000_0020];
+        assert_eq!(foo.callees, [0x8000_0010]);
+        assert!(!foo.indirect_calls);
+    }
+
+    #[test]
+    fn stack_sizes_are_uleb128_after_the_address() {
+        let raw = [
+            0x10, 0x00, 0x00, 0x80, 0x10, // 0x80000010: 16
+            0x20, 0x00, 0x00, 0x80, 0xac, 0x02, // 0x80000020: 300
+            0x30, 0x00, // 不完整的地址被忽略
+        ];
+        assert_eq!(
+            decode_stack_sizes(&raw, 4),
+            [(0x8000_0010, 16), (0x8000_0020, 300)]
+        );
+
+        let raw = [
+            0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x80, 0x80, 0x01,
+        ];
+        assert_eq!(decode_stack_sizes(&raw, 8), [(0x8000_1000, 16384)]);
+    }
+
+    #[test]
+    fn su_lines_give_function_name_and_frame() {
+        let su = "src/uart.c:12:6:uart_init\t32\tstatic\n\
+                  src/main.c:5:5:c_main\t128\tdynamic,bounded\n\
+                  malformed line\n";
+        assert_eq!(
+            parse_su(su),
+            [("uart_init".to_string(), 32), ("c_main".to_string(), 128)]
+        );
+    }
+
+    fn stack_function(name: &str, frame: Option<u64>, callees: &[u64]) -> StackFunction {
+        StackFunction {
+            name: name.to_string(),
+            frame,
+            callees: callees.to_vec(),
+            indirect_calls: false,
+        }
+    }
+
+    #[test]
+    fn worst_stack_path_counts_recursion_once() {
+        // a -> b -> d -> b（递归），a -> c -> a（递归），e 没有栈大小信息
+        let functions = BTreeMap::from([
+            (1, stack_function("a", Some(16), &[2, 3])),
+            (2, stack_function("b", Some(32), &[4])),
+            (3, stack_function("c", Some(8), &[1, 5])),
+            (4, stack_function("d", Some(64), &[2])),
+            (5, stack_function("e", None, &[])),
+        ]);
+
+        let (depth, path, mut recursive) = worst_stack_path(&functions).unwrap();
+        assert_eq!(depth, 16 + 32 + 64);
+        assert_eq!(path, [1, 2, 4]);
+        recursive.sort();
+        assert_eq!(recursive, [1, 2]);
+    }
+
+    #[test]
+    fn worst_stack_path_when_every_function_is_in_a_cycle() {
+        let functions = BTreeMap::from([
+            (1, stack_function("a", Some(16), &[2])),
+            (2, stack_function("b", Some(48), &[1])),
+        ]);
+
+        let (depth, path, recursive) = worst_stack_path(&functions).unwrap();
+        assert_eq!(depth, 64);
+        assert_eq!(path, [1, 2]);
+        assert!(!recursive.is_empty());
+        assert!(worst_stack_path(&BTreeMap::new()).is_none());
+    }
+
+    /// 通过 clap 解析 build 参数
+    fn parse_build(args: &[&str]) -> BuildCommand {
+        #[derive(clap::Parser)]
+        struct Cli {
+            #[command(flatten)]
+            build: BuildCommand,
+        }
+        let args = std::iter::once("build").chain(args.iter().copied());
+        <Cli as clap::Parser>::parse_from(args).build
+    }
+
+    #[test]
+    fn lto_env_var_uses_cargo_profile_name() {
+        assert_eq!(
+            parse_build(&["--lto", "thin"]).lto_env_var(),
+            "CARGO_PROFILE_DEV_LTO"
+        );
+        assert_eq!(
+            parse_build(&["--lto", "fat", "--release"]).lto_env_var(),
+            "CARGO_PROFILE_RELEASE_LTO"
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::cmd::Command;
 use anyhow::Result;
 use clap::Args;
 use console::style;
+use humansize::{DECIMAL, format_size};
 use std::path::Path;
 use std::process::{Command as StdCommand, Stdio};
 
@@ -10,13 +11,31 @@ pub struct CleanCommand {
     /// Clean all artifacts including configs and include directories
     #[arg(short = 'a', long)]
     all: bool,
+
+    /// Only remove post-build artifacts in build/ (keeps target/)
+    #[arg(short = 'b', long, conflicts_with = "all")]
+    build_only: bool,
+
+    /// Keep build/size_history.csv so size trends survive the clean
+    #[arg(long)]
+    keep_history: bool,
 }
 
+// --build-only 删除的文件扩展名
+const BUILD_ARTIFACT_EXTENSIONS: [&str; 4] = ["bin", "hex", "txt", "map"];
+
+// 构建失败时可能残留的结果文件，会误导 CI
+const BUILD_RESULT_FILE: &str = "ecos-build-result.json";
+
 impl Command for CleanCommand {
     fn execute(&self) -> Result<()> {
         let project_root = crate::cmd::find_project_root()?;
         std::env::set_current_dir(&project_root)?;
 
+        if self.build_only {
+            return self.clean_build_artifacts();
+        }
+
         if self.all {
             println!(
                 "{} Cleaning ALL ECOS project artifacts...",
@@ -29,20 +48,36 @@ impl Command for CleanCommand {
             );
         }
 
+        let mut freed = 0;
+
         println!("  🗑️  Running cargo clean...");
+        let target_size = path_size(&crate::cmd::build::target_dir(&project_root));
         let status = StdCommand::new("cargo")
             .arg("clean")
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
             .status()?;
 
-        if !status.success() {
+        if status.success() {
+            freed += target_size;
+        } else {
             println!("{} Cargo clean failed", style("⚠️").yellow());
         }
 
-        if Path::new("build").exists() {
+        let build_dir = Path::new("build");
+        freed += remove_build_result(build_dir);
+        let history = build_dir.join(crate::cmd::build::SIZE_HISTORY_FILE);
+        if self.keep_history && history.exists() {
+            println!("  🗑️  Removing build directory (keeping size history)...");
+            for entry in std::fs::read_dir(build_dir)? {
+                let path = entry?.path();
+                if path != history {
+                    freed += remove_path(&path);
+                }
+            }
+        } else if build_dir.exists() {
             println!("  🗑️  Removing build directory...");
-            let _ = std::fs::remove_dir_all("build");
+            freed += remove_path(build_dir);
         }
 
         if self.all {
@@ -58,21 +93,87 @@ impl Command for CleanCommand {
             for config in &configs_to_clean {
                 if Path::new(config).exists() {
                     println!("    Removing {}...", config);
-                    if Path::new(config).is_dir() {
-                        let _ = std::fs::remove_dir_all(config);
-                    } else {
-                        let _ = std::fs::remove_file(config);
-                    }
+                    freed += remove_path(Path::new(config));
                 }
             }
 
             if Path::new("include").exists() {
                 println!("    Removing include directory...");
-                let _ = std::fs::remove_dir_all("include");
+                freed += remove_path(Path::new("include"));
+            }
+        }
+
+        println!(
+            "✅ Clean completed! Freed {}",
+            style(format_size(freed, DECIMAL)).cyan()
+        );
+        Ok(())
+    }
+}
+
+impl CleanCommand {
+    /// 仅删除 build/ 下的构建产物，不执行 cargo clean
+    fn clean_build_artifacts(&self) -> Result<()> {
+        println!(
+            "{} Cleaning ECOS post-build artifacts...",
+            style("🧹").cyan()
+        );
+
+        let build_dir = Path::new("build");
+        let mut freed = remove_build_result(build_dir);
+        if build_dir.exists() {
+            for entry in std::fs::read_dir(build_dir)? {
+                let path = entry?.path();
+                let is_artifact = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| BUILD_ARTIFACT_EXTENSIONS.contains(&ext))
+                    .unwrap_or(false);
+
+                if path.is_file() && is_artifact {
+                    println!("  🗑️  Removing {}...", path.display());
+                    freed += remove_path(&path);
+                }
             }
         }
 
-        println!("✅ Clean completed!");
+        println!(
+            "✅ Clean completed! Freed {}",
+            style(format_size(freed, DECIMAL)).cyan()
+        );
         Ok(())
     }
 }
+
+/// 删除上次构建残留的 build/ecos-build-result.json
+fn remove_build_result(build_dir: &Path) -> u64 {
+    let result = build_dir.join(BUILD_RESULT_FILE);
+    if !result.is_file() {
+        return 0;
+    }
+
+    println!("  🗑️  Removing stale {}...", result.display());
+    remove_path(&result)
+}
+
+/// 文件或目录（递归）占用的字节数
+fn path_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// 删除文件或目录，返回释放的字节数（删除失败时为 0）
+fn remove_path(path: &Path) -> u64 {
+    let size = path_size(path);
+    let removed = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    if removed.is_ok() { size } else { 0 }
+}
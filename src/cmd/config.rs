@@ -1,10 +1,17 @@
 use crate::cmd::Command;
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use console::style;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command as StdCommand, Stdio};
 
+// 配置片段目录
+const FRAGMENTS_DIR: &str = "configs/fragments";
+
+// source 指令的最大嵌套深度
+const MAX_SOURCE_DEPTH: usize = 8;
+
 #[derive(Args)]
 pub struct ConfigCommand {
     /// Generate default configuration
@@ -14,6 +21,93 @@ pub struct ConfigCommand {
     /// Default configuration name (c1, c2, l3)
     #[arg(long, default_value = "c1")]
     name: String,
+
+    /// Split configs/.config into per-prefix fragments under configs/fragments/
+    /// (optionally only the given prefix, e.g. UART)
+    #[arg(long, value_name = "PREFIX", num_args = 0..=1, default_missing_value = "")]
+    split: Option<String>,
+
+    /// Merge fragments (or `source "..."` directives) back into configs/.config
+    #[arg(long, conflicts_with = "split")]
+    merge: bool,
+
+    /// Set a config option in place, e.g. --set UART_BAUD=115200 (repeatable)
+    #[arg(long, value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Apply all options from the [config] section of a TOML/YAML file
+    #[arg(long, value_name = "FILE")]
+    batch: Option<PathBuf>,
+
+    /// Skip menuconfig and only run syncconfig on the existing configs/.config
+    #[arg(long)]
+    sync_only: bool,
+
+    /// Fail instead of falling back to `conf --oldconfig` when /dev/tty is unavailable
+    #[arg(long)]
+    require_tty: bool,
+
+    /// Convert configs/.config straight to include/generated/autoconf.h without
+    /// running any Kconfig tools (e.g. after editing .config by hand)
+    #[arg(long)]
+    gen_autoconf_only: bool,
+
+    /// Regenerate include/config/auto.conf and include/generated/autoconf.h from
+    /// configs/.config without running any Kconfig binary (no SDK needed)
+    #[arg(long, conflicts_with_all = ["sync_only", "gen_autoconf_only"])]
+    sync_headers_only: bool,
+
+    /// Search Kconfig options whose name, prompt or help text contains PATTERN
+    /// (case-insensitive) and show their current value from configs/.config
+    #[arg(long, value_name = "PATTERN")]
+    kconfig_search: Option<String>,
+
+    /// Output format: `compact` hides the syncconfig output and prints a one-line
+    /// summary of changed keys, `verbose` streams it and lists every change
+    #[arg(long, value_enum, default_value_t = ConfigFormat::Verbose)]
+    format: ConfigFormat,
+
+    /// Whitespace-separated KEY=VALUE environment variables for the mconf process, e.g.
+    /// "KCONFIG_NOTIMESTAMP=1 MENUCONFIG_COLOR=mono". Useful ones: KCONFIG_NOTIMESTAMP,
+    /// MENUCONFIG_COLOR (mono|blackbg|classic|bluetitle), MENUCONFIG_MODE=single_menu,
+    /// KCONFIG_OVERWRITECONFIG
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true, value_parser = crate::cmd::parse_env_pairs)]
+    menuconfig_args: Option<crate::cmd::EnvPairs>,
+
+    /// Restore configs/.config from the most recent backup
+    /// (configs/.config.bak.<timestamp> or configs/.config.old)
+    #[arg(long)]
+    restore_backup: bool,
+}
+
+// --set / --batch 保留的带时间戳备份数量
+const MAX_CONFIG_BACKUPS: usize = 5;
+const CONFIG_BACKUP_PREFIX: &str = ".config.bak.";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ConfigFormat {
+    Compact,
+    Verbose,
+}
+
+/// Kconfig 中的一个配置项
+#[derive(Debug, Default)]
+struct KconfigSymbol {
+    name: String,
+    kind: String,
+    prompt: String,
+    help: String,
+}
+
+/// --set / --batch 单个配置项的应用结果
+#[derive(Debug, PartialEq, Eq)]
+enum SetOutcome {
+    /// 已存在且值相同，或由未设置变为设置
+    Set,
+    /// 已存在且值被修改
+    Changed,
+    /// .config 中不存在，已追加到末尾
+    NotFound,
 }
 
 impl Command for ConfigCommand {
@@ -22,16 +116,91 @@ impl Command for ConfigCommand {
         let project_root = crate::cmd::find_project_root()?;
         std::env::set_current_dir(&project_root)?;
 
-        if self.default {
+        // 执行前的 .config，用于输出变化汇总
+        let config_file = project_root.join("configs/.config");
+        let before = std::fs::read_to_string(&config_file).unwrap_or_default();
+
+        // 修改前先把 .config 保存到 .config.old，后续步骤失败时旧配置仍可恢复；
+        // 搜索和恢复备份不修改 .config，不覆盖 .config.old
+        let config_old = project_root.join("configs/.config.old");
+        let mutating = self.kconfig_search.is_none() && !self.restore_backup;
+        let previous_old = std::fs::read_to_string(&config_old).ok();
+        if mutating && !before.is_empty() {
+            std::fs::write(&config_old, &before)?;
+        }
+
+        if let Some(pattern) = &self.kconfig_search {
+            self.kconfig_search(&project_root, pattern)?;
+        } else if self.restore_backup {
+            self.restore_backup(&project_root)?;
+        } else if let Some(prefix) = &self.split {
+            self.split_config(&project_root, prefix)?;
+        } else if self.merge {
+            self.merge_config(&project_root)?;
+        } else if !self.set.is_empty() || self.batch.is_some() {
+            if config_file.exists() {
+                backup_config(&project_root)?;
+            }
+            self.apply_settings(&project_root)?;
+        } else if self.gen_autoconf_only {
+            self.gen_autoconf_only(&project_root)?;
+        } else if self.sync_headers_only {
+            self.sync_headers_only(&project_root)?;
+        } else if self.sync_only {
+            self.run_sync_only(&project_root)?;
+        } else if self.default {
             self.generate_default_config(&project_root)?;
         } else {
             self.run_menuconfig(&project_root)?;
         }
+
+        if self.kconfig_search.is_none() {
+            let after = std::fs::read_to_string(&config_file).unwrap_or_default();
+
+            // .config 没有变化时还原原来的 .config.old
+            if mutating && !before.is_empty() && after == before {
+                match &previous_old {
+                    Some(content) => std::fs::write(&config_old, content)?,
+                    None => std::fs::remove_file(&config_old)?,
+                }
+            }
+
+            self.print_config_changes(&before, &after);
+        }
         Ok(())
     }
 }
 
 impl ConfigCommand {
+    /// --restore-backup：用最近的 .config.bak.<时间戳> 覆盖 configs/.config，
+    /// 没有带时间戳的备份时才使用 .config.old
+    fn restore_backup(&self, project_root: &Path) -> Result<()> {
+        let configs_dir = project_root.join("configs");
+        let backup = config_backups(&configs_dir)
+            .pop()
+            .or_else(|| Some(configs_dir.join(".config.old")).filter(|p| p.exists()))
+            .ok_or_else(|| anyhow::anyhow!("No backup of configs/.config found"))?;
+
+        std::fs::copy(&backup, configs_dir.join(".config"))?;
+        println!(
+            "✅ Restored configs/.config from {}",
+            style(backup.display()).cyan()
+        );
+        println!(
+            "  {} Run 'cargo ecos config --sync-only' to regenerate the headers",
+            style("💡").dim()
+        );
+        Ok(())
+    }
+
+    /// --menuconfig-args 中的环境变量
+    fn menuconfig_envs(&self) -> Vec<(String, String)> {
+        self.menuconfig_args
+            .as_ref()
+            .map(|pairs| pairs.0.clone())
+            .unwrap_or_default()
+    }
+
     fn run_menuconfig(&self, project_root: &Path) -> Result<()> {
         println!("{} Running menuconfig...", style("📋").cyan());
 
@@ -65,34 +234,57 @@ impl ConfigCommand {
         let kconfig_file = sdk_path.join("tools/kconfig/Kconfig");
         println!("  Using Kconfig: {}", style(kconfig_file.display()).dim());
 
-        let status = StdCommand::new(&mconf)
-            .arg(&kconfig_file)
-            .env("KCONFIG_CONFIG", &config_file)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
+        // CI 等环境下没有终端，mconf 无法打开 /dev/tty
+        if tty_available() {
+            let status = StdCommand::new(&mconf)
+                .arg(&kconfig_file)
+                .env("KCONFIG_CONFIG", &config_file)
+                .envs(self.menuconfig_envs())
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()?;
 
-        if !status.success() {
-            return Err(anyhow::anyhow!("menuconfig failed"));
+            if !status.success() {
+                return Err(anyhow::anyhow!("menuconfig failed"));
+            }
+        } else if self.require_tty {
+            return Err(anyhow::anyhow!(
+                "/dev/tty is not available, cannot run menuconfig (--require-tty)"
+            ));
+        } else {
+            println!(
+                "{} /dev/tty is not available, falling back to {}",
+                style("⚠️").yellow(),
+                style("conf --oldconfig").cyan()
+            );
+
+            // 没有终端输入时，新增选项取默认值
+            let status = StdCommand::new(&conf)
+                .args(["--oldconfig", kconfig_file.to_str().unwrap()])
+                .env("KCONFIG_CONFIG", &config_file)
+                .envs(self.menuconfig_envs())
+                .stdin(Stdio::null())
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()?;
+
+            if !status.success() {
+                return Err(anyhow::anyhow!("conf --oldconfig failed"));
+            }
         }
 
         // 运行 syncconfig，直接输出到项目目录
         println!("{} Synchronizing configuration...", style("🔄").cyan());
 
         // 设置环境变量，让 Kconfig 输出到项目目录
-        let status = StdCommand::new(&conf)
-            .args(&["--syncconfig", kconfig_file.to_str().unwrap()])
-            .env("KCONFIG_CONFIG", &config_file)
-            .env("OUTPUT", project_root.join("include")) // 关键：指定输出目录
-            .env("CONFIG_", "CONFIG_")
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
-
-        if !status.success() {
-            return Err(anyhow::anyhow!("Failed to sync config"));
-        }
+        self.run_syncconfig(
+            StdCommand::new(&conf)
+                .args(["--syncconfig", kconfig_file.to_str().unwrap()])
+                .env("KCONFIG_CONFIG", &config_file)
+                .env("OUTPUT", project_root.join("include")) // 关键：指定输出目录
+                .env("CONFIG_", "CONFIG_"),
+        )?;
 
         // 清理不需要的中间文件
         self.cleanup_generated_files(project_root, &sdk_path)?;
@@ -106,6 +298,344 @@ impl ConfigCommand {
         Ok(())
     }
 
+    /// 将 .config 按 CONFIG_ 前缀拆分为多个片段文件
+    fn split_config(&self, project_root: &Path, prefix: &str) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        if !config_file.exists() {
+            return Err(anyhow::anyhow!(
+                "configs/.config not found. Run 'cargo ecos config' first."
+            ));
+        }
+
+        let wanted = prefix
+            .trim_end_matches(['*', '_'])
+            .trim_start_matches("CONFIG_")
+            .to_uppercase();
+
+        println!("{} Splitting configuration...", style("✂️").cyan());
+
+        let content = expand_config_sources(&config_file, 0)?;
+        // 指定前缀时只生成一个以完整前缀命名的片段，如 UART_BAUD -> uart_baud.config
+        let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for line in content.lines() {
+            let group = if wanted.is_empty() {
+                config_group(line)
+            } else {
+                config_name(line)
+                    .filter(|name| option_has_prefix(name, &wanted))
+                    .map(|_| wanted.clone())
+            };
+            if let Some(group) = group {
+                groups.entry(group).or_default().push(line);
+            }
+        }
+
+        if groups.is_empty() {
+            println!("{} No matching options found", style("⚠️").yellow());
+            return Ok(());
+        }
+
+        let fragments_dir = project_root.join(FRAGMENTS_DIR);
+        std::fs::create_dir_all(&fragments_dir)?;
+
+        for (group, lines) in &groups {
+            let fragment = fragments_dir.join(format!("{}.config", group.to_lowercase()));
+            let mut output = format!("# CONFIG_{}_* options\n", group);
+            for line in lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+            std::fs::write(&fragment, output)?;
+            println!(
+                "  Wrote {} ({} options)",
+                style(fragment.display()).dim(),
+                lines.len()
+            );
+        }
+
+        println!(
+            "✅ Split into {} fragment(s) under {}",
+            groups.len(),
+            style(FRAGMENTS_DIR).cyan()
+        );
+
+        Ok(())
+    }
+
+    /// 将片段文件合并回 .config
+    fn merge_config(&self, project_root: &Path) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        let fragments_dir = project_root.join(FRAGMENTS_DIR);
+
+        println!("{} Merging configuration fragments...", style("🧩").cyan());
+
+        // .config 中含有 source 指令时，直接展开；否则合并 fragments 目录下的所有片段
+        let has_sources = std::fs::read_to_string(&config_file)
+            .map(|content| content.lines().any(|line| parse_source(line).is_some()))
+            .unwrap_or(false);
+
+        let output = if has_sources {
+            let mut output = String::from("# ECOS Configuration\n# Merged by cargo-ecos\n");
+            for line in expand_config_sources(&config_file, 0)?.lines() {
+                if config_group(line).is_some() {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+            }
+            output
+        } else {
+            if !fragments_dir.exists() {
+                return Err(anyhow::anyhow!(
+                    "No fragments found in {}. Run 'cargo ecos config --split' first.",
+                    FRAGMENTS_DIR
+                ));
+            }
+
+            let mut fragments: Vec<PathBuf> = std::fs::read_dir(&fragments_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().map(|e| e == "config").unwrap_or(false))
+                .collect();
+            fragments.sort();
+
+            let mut merged = String::new();
+            for fragment in &fragments {
+                println!("  Merging {}", style(fragment.display()).dim());
+                merged.push_str(&expand_config_sources(fragment, 0)?);
+            }
+
+            // 片段只覆盖其中出现的选项，.config 中的其他选项保持不变
+            let existing = std::fs::read_to_string(&config_file)
+                .unwrap_or_else(|_| "# ECOS Configuration\n# Merged by cargo-ecos\n".to_string());
+            merge_fragments(&existing, &merged)
+        };
+
+        std::fs::create_dir_all(project_root.join("configs"))?;
+        std::fs::write(&config_file, output)?;
+
+        println!(
+            "✅ Configuration merged into {}",
+            style("configs/.config").cyan()
+        );
+        println!("  Run 'cargo ecos config' to regenerate headers");
+
+        Ok(())
+    }
+
+    /// 应用 --set 与 --batch 中的配置项，最后执行一次 syncconfig
+    fn apply_settings(&self, project_root: &Path) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        if !config_file.exists() {
+            return Err(anyhow::anyhow!(
+                "configs/.config not found. Run 'cargo ecos config' first."
+            ));
+        }
+
+        let sdk_home = crate::cmd::check_sdk_home()?;
+
+        let mut settings = Vec::new();
+        for pair in &self.set {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid --set '{}', expected KEY=VALUE", pair))?;
+            settings.push((key.trim().to_string(), value.trim().to_string()));
+        }
+        if let Some(batch) = &self.batch {
+            settings.extend(read_batch_file(batch)?);
+        }
+
+        println!(
+            "{} Applying {} config option(s)...",
+            style("📝").cyan(),
+            settings.len()
+        );
+
+        let mut content = std::fs::read_to_string(&config_file)?;
+        for (key, value) in &settings {
+            let (updated, outcome) = apply_config_value(&content, key, value);
+            content = updated;
+
+            let name = normalize_config_key(key);
+            match outcome {
+                SetOutcome::Set => println!("  {} {}={}", style("set").green(), name, value),
+                SetOutcome::Changed => {
+                    println!("  {} {}={}", style("changed").cyan(), name, value)
+                }
+                SetOutcome::NotFound => println!(
+                    "  {} {} (appended, may be dropped by syncconfig)",
+                    style("not found").yellow(),
+                    name
+                ),
+            }
+        }
+        std::fs::write(&config_file, content)?;
+
+        self.sync_config(project_root, &PathBuf::from(&sdk_home))?;
+
+        println!(
+            "✅ Configuration updated in {}",
+            style("configs/.config").cyan()
+        );
+
+        Ok(())
+    }
+
+    /// 跳过 menuconfig，仅根据现有 .config 重新生成头文件
+    fn run_sync_only(&self, project_root: &Path) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        if !config_file.exists() {
+            return Err(anyhow::anyhow!(
+                "configs/.config not found. Run 'cargo ecos config' first."
+            ));
+        }
+
+        println!("{} Synchronizing configuration...", style("🔄").cyan());
+
+        let sdk_home = crate::cmd::check_sdk_home()?;
+        std::fs::create_dir_all(project_root.join("include/generated"))?;
+        std::fs::create_dir_all(project_root.join("include/config"))?;
+
+        let before = snapshot_headers(project_root);
+        self.sync_config(project_root, &PathBuf::from(&sdk_home))?;
+        let after = snapshot_headers(project_root);
+
+        let changed: Vec<&PathBuf> = after
+            .iter()
+            .filter(|(path, content)| before.get(*path) != Some(content))
+            .map(|(path, _)| path)
+            .collect();
+
+        if changed.is_empty() {
+            println!("  No header changes");
+        } else {
+            println!("  Changed headers:");
+            for path in changed {
+                println!("    {}", style(path.display()).dim());
+            }
+        }
+
+        println!("✅ Headers regenerated in {}", style("include/").cyan());
+        Ok(())
+    }
+
+    /// 解析 SDK 的 Kconfig 文件，按名称/提示/帮助文本搜索配置项
+    fn kconfig_search(&self, project_root: &Path, pattern: &str) -> Result<()> {
+        let sdk_path = PathBuf::from(crate::cmd::check_sdk_home()?);
+        let kconfig_file = sdk_path.join("tools/kconfig/Kconfig");
+
+        let mut symbols = Vec::new();
+        parse_kconfig(&kconfig_file, &sdk_path, 0, &mut symbols)?;
+
+        let needle = pattern.to_lowercase();
+        let matches: Vec<&KconfigSymbol> = symbols
+            .iter()
+            .filter(|sym| {
+                sym.name.to_lowercase().contains(&needle)
+                    || sym.prompt.to_lowercase().contains(&needle)
+                    || sym.help.to_lowercase().contains(&needle)
+            })
+            .collect();
+
+        if matches.is_empty() {
+            println!(
+                "{} No Kconfig option matches '{}'",
+                style("🔍").cyan(),
+                pattern
+            );
+            return Ok(());
+        }
+
+        // 当前 .config 中的值
+        let config =
+            std::fs::read_to_string(project_root.join("configs/.config")).unwrap_or_default();
+        let current_value = |name: &str| {
+            let key = format!("CONFIG_{}=", name);
+            let unset = format!("# CONFIG_{} is not set", name);
+            config.lines().find_map(|line| {
+                if let Some(value) = line.strip_prefix(&key) {
+                    Some(value.to_string())
+                } else if line.trim() == unset {
+                    Some("n".to_string())
+                } else {
+                    None
+                }
+            })
+        };
+
+        let width = matches.iter().map(|s| s.name.len()).max().unwrap_or(0) + 7;
+        println!(
+            "{} {} option(s) matching '{}':",
+            style("🔍").cyan(),
+            matches.len(),
+            pattern
+        );
+        println!(
+            "  {:<width$}  {:<8}  {:<16}  Prompt",
+            "Option", "Type", "Value"
+        );
+        for sym in matches {
+            let value = current_value(&sym.name).unwrap_or_else(|| "-".to_string());
+            println!(
+                "  {:<width$}  {:<8}  {:<16}  {}",
+                style(format!("CONFIG_{}", sym.name)).cyan(),
+                sym.kind,
+                value,
+                style(&sym.prompt).dim()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// 不依赖 Kconfig 工具，直接由 configs/.config 生成 autoconf.h
+    fn gen_autoconf_only(&self, project_root: &Path) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        if !config_file.exists() {
+            return Err(anyhow::anyhow!(
+                "configs/.config not found. Run 'cargo ecos config --default' first."
+            ));
+        }
+
+        let autoconf_h = project_root.join("include/generated/autoconf.h");
+        self.convert_auto_conf_to_autoconf_h(&config_file, &autoconf_h)?;
+
+        println!(
+            "✅ Generated {} from {}",
+            style("include/generated/autoconf.h").cyan(),
+            style("configs/.config").dim()
+        );
+        Ok(())
+    }
+
+    /// 不依赖 Kconfig 工具，按 syncconfig 的格式由 configs/.config 生成 auto.conf 与 autoconf.h
+    fn sync_headers_only(&self, project_root: &Path) -> Result<()> {
+        let config_file = project_root.join("configs/.config");
+        if !config_file.exists() {
+            return Err(anyhow::anyhow!(
+                "configs/.config not found. Run 'cargo ecos config --default' first."
+            ));
+        }
+
+        let before = snapshot_headers(project_root);
+        let auto_conf = project_root.join("include/config/auto.conf");
+        let autoconf_h = project_root.join("include/generated/autoconf.h");
+        self.convert_auto_conf_to_autoconf_h(&config_file, &autoconf_h)?;
+        self.write_auto_conf(&config_file, &auto_conf)?;
+        let after = snapshot_headers(project_root);
+
+        let changed = after
+            .iter()
+            .filter(|(path, content)| before.get(*path) != Some(content))
+            .count();
+        println!(
+            "✅ Synchronized {} and {} from {} ({} file(s) changed)",
+            style("include/config/auto.conf").cyan(),
+            style("include/generated/autoconf.h").cyan(),
+            style("configs/.config").dim(),
+            changed
+        );
+        Ok(())
+    }
+
     fn build_kconfig_tools(&self, sdk_path: &Path) -> Result<()> {
         let kconfig_dir = sdk_path.join("tools/kconfig");
 
@@ -211,18 +741,13 @@ impl ConfigCommand {
         let kconfig_file = sdk_path.join("tools/kconfig/Kconfig");
         let config_file = project_root.join("configs/.config");
 
-        let status = StdCommand::new(&conf)
-            .args(&["--syncconfig", kconfig_file.to_str().unwrap()])
-            .env("KCONFIG_CONFIG", &config_file)
-            .env("OUTPUT", project_root.join("include")) // 关键：指定输出目录
-            .env("CONFIG_", "CONFIG_")
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()?;
-
-        if !status.success() {
-            return Err(anyhow::anyhow!("Failed to sync config"));
-        }
+        self.run_syncconfig(
+            StdCommand::new(&conf)
+                .args(["--syncconfig", kconfig_file.to_str().unwrap()])
+                .env("KCONFIG_CONFIG", &config_file)
+                .env("OUTPUT", project_root.join("include")) // 关键：指定输出目录
+                .env("CONFIG_", "CONFIG_"),
+        )?;
 
         // 清理不需要的中间文件
         self.cleanup_generated_files(project_root, sdk_path)?;
@@ -230,6 +755,74 @@ impl ConfigCommand {
         Ok(())
     }
 
+    /// 运行 syncconfig：compact 模式下捕获输出，仅在失败时打印
+    fn run_syncconfig(&self, command: &mut StdCommand) -> Result<()> {
+        let success = match self.format {
+            ConfigFormat::Compact => {
+                let output = command.output()?;
+                if !output.status.success() {
+                    eprint!("{}", String::from_utf8_lossy(&output.stdout));
+                    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+                }
+                output.status.success()
+            }
+            ConfigFormat::Verbose => command
+                .stdout(Stdio::inherit())
+                .stderr(Stdio::inherit())
+                .status()?
+                .success(),
+        };
+
+        if !success {
+            return Err(anyhow::anyhow!("Failed to sync config"));
+        }
+        Ok(())
+    }
+
+    /// 比较执行前后的 .config：compact 输出一行汇总，verbose 列出每一项
+    fn print_config_changes(&self, before: &str, after: &str) {
+        let before = config_values(before);
+        let after = config_values(after);
+
+        let added: Vec<_> = after.keys().filter(|k| !before.contains_key(*k)).collect();
+        let removed: Vec<_> = before.keys().filter(|k| !after.contains_key(*k)).collect();
+        let changed: Vec<_> = after
+            .iter()
+            .filter(|(k, v)| before.get(*k).is_some_and(|old| old != *v))
+            .map(|(k, _)| k)
+            .collect();
+
+        if self.format == ConfigFormat::Compact {
+            if added.is_empty() && removed.is_empty() && changed.is_empty() {
+                println!("Config unchanged");
+            } else {
+                println!(
+                    "Config updated: +{} -{} ~{} keys",
+                    added.len(),
+                    removed.len(),
+                    changed.len()
+                );
+            }
+            return;
+        }
+
+        for key in added {
+            println!("  {} {}={}", style("+").green(), key, after[key]);
+        }
+        for key in removed {
+            println!("  {} {}={}", style("-").red(), key, before[key]);
+        }
+        for key in changed {
+            println!(
+                "  {} {}: {} -> {}",
+                style("~").yellow(),
+                key,
+                before[key],
+                after[key]
+            );
+        }
+    }
+
     fn cleanup_generated_files(&self, project_root: &Path, sdk_path: &Path) -> Result<()> {
         // 检查 autoconf.h
         let autoconf_h = project_root.join("include/generated/autoconf.h");
@@ -295,41 +888,671 @@ impl ConfigCommand {
             Err(_) => return Ok(()),
         };
 
-        let mut output = String::new();
-        output.push_str("/* Automatically generated file; DO NOT EDIT. */\n");
-        output.push_str("#ifndef __AUTOCONF_H__\n");
-        output.push_str("#define __AUTOCONF_H__\n\n");
+        let output = auto_conf_to_header(&content);
 
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("CONFIG_") {
-                let parts: Vec<&str> = trimmed.splitn(2, '=').collect();
-                if parts.len() == 2 {
-                    let name = parts[0].trim();
-                    let value = parts[1].trim();
-
-                    if value == "y" || value == "\"y\"" {
-                        output.push_str(&format!("#define {} 1\n", name));
-                    } else if value == "n" || value == "\"n\"" {
-                        output.push_str(&format!("/* #undef {} */\n", name));
-                    } else if value.starts_with('"') && value.ends_with('"') {
-                        let str_value = &value[1..value.len() - 1];
-                        output.push_str(&format!("#define {} \"{}\"\n", name, str_value));
-                    } else {
-                        output.push_str(&format!("#define {} {}\n", name, value));
+        // 确保目录存在
+        if let Some(parent) = autoconf_h_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(autoconf_h_path, output)?;
+        Ok(())
+    }
+
+    /// 由 .config 生成 include/config/auto.conf（只保留已设置的项）
+    fn write_auto_conf(&self, config_path: &Path, auto_conf_path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(config_path)?;
+
+        if let Some(parent) = auto_conf_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(auto_conf_path, config_to_auto_conf(&content))?;
+        Ok(())
+    }
+}
+
+/// 获取配置行中的选项名（不含 CONFIG_），如 CONFIG_UART_BAUD=115200 -> UART_BAUD
+fn config_name(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let name = if let Some(rest) = trimmed.strip_prefix("# ") {
+        rest.strip_suffix(" is not set")?
+    } else {
+        trimmed.split('=').next()?
+    };
+
+    name.trim()
+        .strip_prefix("CONFIG_")
+        .filter(|name| !name.is_empty())
+}
+
+/// 选项名是否以 prefix 为完整的分段前缀，如 UART_BAUD 匹配 UART 和 UART_BAUD，但不匹配 UAR
+fn option_has_prefix(name: &str, prefix: &str) -> bool {
+    let name = name.to_uppercase();
+    name == prefix
+        || name
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('_'))
+}
+
+/// 用片段中的选项覆盖 .config 中的同名选项，新增的选项追加到末尾
+fn merge_fragments(existing: &str, fragments: &str) -> String {
+    let mut overrides: Vec<(&str, &str)> = Vec::new();
+    for line in fragments.lines() {
+        if let Some(name) = config_name(line) {
+            // 同一选项出现多次时以最后一次为准
+            overrides.retain(|(n, _)| *n != name);
+            overrides.push((name, line.trim()));
+        }
+    }
+
+    let mut output = String::new();
+    let mut applied = Vec::new();
+    for line in existing.lines() {
+        match config_name(line).and_then(|name| overrides.iter().find(|(n, _)| *n == name)) {
+            Some((name, replacement)) => {
+                output.push_str(replacement);
+                applied.push(*name);
+            }
+            None => output.push_str(line),
+        }
+        output.push('\n');
+    }
+    for (name, line) in &overrides {
+        if !applied.contains(name) {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// 获取配置行所属的分组，如 CONFIG_UART_BAUD=115200 -> UART
+fn config_group(line: &str) -> Option<String> {
+    config_name(line)?
+        .split('_')
+        .next()
+        .filter(|group| !group.is_empty())
+        .map(|group| group.to_uppercase())
+}
+
+/// 解析 source "path" 指令
+fn parse_source(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("source")
+        .map(|rest| rest.trim().trim_matches('"'))
+        .filter(|path| !path.is_empty())
+}
+
+/// 读取配置文件，并递归展开其中的 source 指令（路径相对于当前文件所在目录）
+fn expand_config_sources(path: &Path, depth: usize) -> Result<String> {
+    if depth > MAX_SOURCE_DEPTH {
+        return Err(anyhow::anyhow!(
+            "source directives nested too deeply at {}",
+            path.display()
+        ));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut output = String::new();
+    for line in content.lines() {
+        if let Some(source) = parse_source(line) {
+            output.push_str(&expand_config_sources(&base_dir.join(source), depth + 1)?);
+        } else {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    Ok(output)
+}
+
+/// 补全 CONFIG_ 前缀
+fn normalize_config_key(key: &str) -> String {
+    if key.starts_with("CONFIG_") {
+        key.to_string()
+    } else {
+        format!("CONFIG_{}", key)
+    }
+}
+
+/// 生成 .config 中的一行；值为 n 时写成 "is not set" 注释
+fn format_config_line(name: &str, value: &str) -> String {
+    let is_raw = matches!(value, "y" | "m")
+        || value.parse::<i64>().is_ok()
+        || value.starts_with("0x")
+        || (value.starts_with('"') && value.ends_with('"') && value.len() >= 2);
+
+    if value == "n" {
+        format!("# {} is not set", name)
+    } else if is_raw {
+        format!("{}={}", name, value)
+    } else {
+        format!("{}=\"{}\"", name, value.replace('"', "\\\""))
+    }
+}
+
+/// 在 .config 内容中原地设置一个配置项
+fn apply_config_value(content: &str, key: &str, value: &str) -> (String, SetOutcome) {
+    let name = normalize_config_key(key);
+    let new_line = format_config_line(&name, value);
+    let unset_line = format!("# {} is not set", name);
+    let assign_prefix = format!("{}=", name);
+
+    let mut outcome = SetOutcome::NotFound;
+    let mut output = String::with_capacity(content.len() + new_line.len());
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if outcome == SetOutcome::NotFound
+            && (trimmed.starts_with(&assign_prefix) || trimmed == unset_line)
+        {
+            outcome = if trimmed == new_line || trimmed == unset_line {
+                SetOutcome::Set
+            } else {
+                SetOutcome::Changed
+            };
+            output.push_str(&new_line);
+        } else {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+
+    if outcome == SetOutcome::NotFound {
+        output.push_str(&new_line);
+        output.push('\n');
+    }
+
+    (output, outcome)
+}
+
+/// 读取 --batch 文件中 [config] 段的 KEY = VALUE 列表
+fn read_batch_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    let is_yaml = path
+        .extension()
+        .map(|ext| ext == "yaml" || ext == "yml")
+        .unwrap_or(false);
+
+    if is_yaml {
+        return Ok(read_yaml_config_section(&content));
+    }
+
+    let batch: toml::Value = toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid batch file {}: {}", path.display(), e))?;
+    let table = batch
+        .get("config")
+        .and_then(|c| c.as_table())
+        .ok_or_else(|| anyhow::anyhow!("No [config] section in {}", path.display()))?;
+
+    Ok(table
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                toml::Value::Boolean(true) => "y".to_string(),
+                toml::Value::Boolean(false) => "n".to_string(),
+                toml::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), value)
+        })
+        .collect())
+}
+
+/// 读取 YAML 中 `config:` 下的平铺键值对（仅支持简单的 KEY: VALUE 形式）
+fn read_yaml_config_section(content: &str) -> Vec<(String, String)> {
+    let mut settings = Vec::new();
+    let mut in_config = false;
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with([' ', '\t']) {
+            in_config = line.trim_end() == "config:";
+            continue;
+        }
+
+        if in_config && let Some((key, value)) = line.trim().split_once(':') {
+            let value = match value.trim() {
+                "true" => "y".to_string(),
+                "false" => "n".to_string(),
+                v => v.trim_matches('\'').to_string(),
+            };
+            settings.push((key.trim().to_string(), value));
+        }
+    }
+
+    settings
+}
+
+// Kconfig 的类型关键字，可带提示字符串，如 bool "Enable UART"
+const KCONFIG_TYPES: [&str; 6] = ["bool", "tristate", "int", "hex", "string", "def_bool"];
+
+/// 解析 Kconfig 文件（递归处理 source/rsource/osource），收集所有 config 项
+fn parse_kconfig(
+    path: &Path,
+    sdk_path: &Path,
+    depth: usize,
+    symbols: &mut Vec<KconfigSymbol>,
+) -> Result<()> {
+    if depth > MAX_SOURCE_DEPTH {
+        return Err(anyhow::anyhow!(
+            "Kconfig source directives nested too deeply at {}",
+            path.display()
+        ));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    let mut current: Option<KconfigSymbol> = None;
+    let mut in_help = false;
+    let mut help_indent = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        // help 文本一直持续到缩进小于第一行 help 文本的位置
+        if in_help {
+            let indent = line.len() - line.trim_start().len();
+            if trimmed.is_empty() {
+                continue;
+            }
+            match help_indent {
+                None => help_indent = Some(indent),
+                Some(first) if indent < first => {
+                    in_help = false;
+                    help_indent = None;
+                }
+                _ => {}
+            }
+            if in_help {
+                if let Some(sym) = current.as_mut() {
+                    if !sym.help.is_empty() {
+                        sym.help.push(' ');
                     }
+                    sym.help.push_str(trimmed);
                 }
+                continue;
             }
         }
 
-        output.push_str("\n#endif /* __AUTOCONF_H__ */\n");
+        let (keyword, rest) = trimmed
+            .split_once(char::is_whitespace)
+            .map(|(k, r)| (k, r.trim()))
+            .unwrap_or((trimmed, ""));
 
-        // 确保目录存在
-        if let Some(parent) = autoconf_h_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        match keyword {
+            "config" | "menuconfig" => {
+                symbols.extend(current.take());
+                current = Some(KconfigSymbol {
+                    name: rest.to_string(),
+                    ..Default::default()
+                });
+            }
+            "help" | "---help---" => in_help = true,
+            "prompt" => {
+                if let Some(sym) = current.as_mut() {
+                    sym.prompt = kconfig_string(rest);
+                }
+            }
+            "source" | "rsource" | "osource" | "orsource" => {
+                symbols.extend(current.take());
+                let file = kconfig_string(rest);
+                // rsource 相对当前文件，source 相对 SDK 根目录（找不到时再相对当前文件）
+                let candidates = if matches!(keyword, "rsource" | "orsource") {
+                    vec![base_dir.join(&file)]
+                } else {
+                    vec![sdk_path.join(&file), base_dir.join(&file)]
+                };
+                match candidates.into_iter().find(|p| p.exists()) {
+                    Some(source) => parse_kconfig(&source, sdk_path, depth + 1, symbols)?,
+                    None if keyword.starts_with('o') => {}
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "Kconfig source '{}' not found (from {})",
+                            file,
+                            path.display()
+                        ));
+                    }
+                }
+            }
+            "menu" | "endmenu" | "choice" | "endchoice" | "if" | "endif" | "comment"
+            | "mainmenu" => {
+                symbols.extend(current.take());
+            }
+            kind if KCONFIG_TYPES.contains(&kind) => {
+                if let Some(sym) = current.as_mut() {
+                    sym.kind = kind.trim_start_matches("def_").to_string();
+                    if !rest.is_empty() && kind != "def_bool" {
+                        sym.prompt = kconfig_string(rest);
+                    }
+                }
+            }
+            _ => {}
         }
+    }
 
-        std::fs::write(autoconf_h_path, output)?;
-        Ok(())
+    symbols.extend(current);
+    Ok(())
+}
+
+/// 取出 Kconfig 行中的引号字符串，忽略后面的 if 条件
+fn kconfig_string(rest: &str) -> String {
+    match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or_default().to_string(),
+        None => rest
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// 将 .config 的内容转换为 syncconfig 格式的 auto.conf：
+/// 去掉注释与 "is not set" 项，其余 CONFIG_X=value 原样保留
+fn config_to_auto_conf(content: &str) -> String {
+    let mut output = String::new();
+    output.push_str("#\n# Automatically generated file; DO NOT EDIT.\n#\n");
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some((name, value)) = trimmed.split_once('=')
+            && name.starts_with("CONFIG_")
+            && value != "n"
+        {
+            output.push_str(&format!("{}={}\n", name, value));
+        }
+    }
+
+    output
+}
+
+/// 将 auto.conf / .config 的内容转换为 autoconf.h：
+/// y -> 1，m -> X_MODULE 1，n -> #undef 注释，字符串保留引号，数值（含十六进制）原样输出
+fn auto_conf_to_header(content: &str) -> String {
+    let mut output = String::new();
+    output.push_str("/* Automatically generated file; DO NOT EDIT. */\n");
+    output.push_str("#ifndef __AUTOCONF_H__\n");
+    output.push_str("#define __AUTOCONF_H__\n\n");
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("CONFIG_") {
+            let parts: Vec<&str> = trimmed.splitn(2, '=').collect();
+            if parts.len() == 2 {
+                let name = parts[0].trim();
+                let value = parts[1].trim();
+
+                if value == "y" || value == "\"y\"" {
+                    output.push_str(&format!("#define {} 1\n", name));
+                } else if value == "m" {
+                    output.push_str(&format!("#define {}_MODULE 1\n", name));
+                } else if value == "n" || value == "\"n\"" {
+                    output.push_str(&format!("/* #undef {} */\n", name));
+                } else if value.starts_with('"') && value.ends_with('"') {
+                    let str_value = &value[1..value.len() - 1];
+                    output.push_str(&format!("#define {} \"{}\"\n", name, str_value));
+                } else {
+                    output.push_str(&format!("#define {} {}\n", name, value));
+                }
+            }
+        }
+    }
+
+    output.push_str("\n#endif /* __AUTOCONF_H__ */\n");
+    output
+}
+
+/// 解析 .config 中的配置项，"# CONFIG_X is not set" 记为 n
+fn config_values(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(name) = line
+                .strip_prefix("# ")
+                .and_then(|rest| rest.strip_suffix(" is not set"))
+            {
+                return Some((name.to_string(), "n".to_string()));
+            }
+            let (key, value) = line.split_once('=')?;
+            key.starts_with("CONFIG_")
+                .then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// 复制 .config 为 configs/.config.bak.<时间戳>，只保留最近 MAX_CONFIG_BACKUPS 个
+fn backup_config(project_root: &Path) -> Result<()> {
+    let configs_dir = project_root.join("configs");
+    let backup = configs_dir.join(format!(
+        "{}{}",
+        CONFIG_BACKUP_PREFIX,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::copy(configs_dir.join(".config"), &backup)?;
+    println!("  Backed up .config to {}", style(backup.display()).dim());
+
+    let backups = config_backups(&configs_dir);
+    for old in &backups[..backups.len().saturating_sub(MAX_CONFIG_BACKUPS)] {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
+
+/// configs/ 下的带时间戳备份，按时间从旧到新排序
+fn config_backups(configs_dir: &Path) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(configs_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(CONFIG_BACKUP_PREFIX))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // 时间戳格式固定，按文件名排序即按时间排序
+    backups.sort();
+    backups
+}
+
+/// 检查 /dev/tty 是否存在、为字符设备且可以打开
+#[cfg(unix)]
+fn tty_available() -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    let is_char_device = std::fs::metadata("/dev/tty")
+        .map(|m| m.file_type().is_char_device())
+        .unwrap_or(false);
+
+    // 容器中 /dev/tty 可能存在但没有控制终端，打开会失败
+    is_char_device
+        && std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .is_ok()
+}
+
+#[cfg(not(unix))]
+fn tty_available() -> bool {
+    true
+}
+
+/// 记录 include/ 下头文件（.h 与 auto.conf）的内容，用于比较变化
+fn snapshot_headers(project_root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    walkdir::WalkDir::new(project_root.join("include"))
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            let path = entry.path();
+            path.extension().map(|ext| ext == "h").unwrap_or(false)
+                || path.file_name().map(|n| n == "auto.conf").unwrap_or(false)
+        })
+        .filter_map(|entry| {
+            let content = std::fs::read(entry.path()).ok()?;
+            let relative = entry.path().strip_prefix(project_root).ok()?.to_path_buf();
+            Some((relative, content))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_lines(content: &str) -> Vec<String> {
+        auto_conf_to_header(content)
+            .lines()
+            .filter(|line| line.contains("CONFIG_"))
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn bool_values_become_defines_and_undefs() {
+        assert_eq!(
+            header_lines("CONFIG_UART=y\nCONFIG_SPI=n\n"),
+            ["#define CONFIG_UART 1", "/* #undef CONFIG_SPI */"]
+        );
+    }
+
+    #[test]
+    fn numeric_hex_and_string_values_are_kept() {
+        assert_eq!(
+            header_lines(
+                "CONFIG_UART_BAUD=115200\n\
+                 CONFIG_LOAD_ADDR=0x30000000\n\
+                 CONFIG_BOARD_NAME=\"ecos-c1\"\n"
+            ),
+            [
+                "#define CONFIG_UART_BAUD 115200",
+                "#define CONFIG_LOAD_ADDR 0x30000000",
+                "#define CONFIG_BOARD_NAME \"ecos-c1\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_and_unset_options_are_skipped() {
+        let header = auto_conf_to_header("#\n# CONFIG_DEBUG is not set\n\nCONFIG_LED=y\n");
+        assert!(header.starts_with("/* Automatically generated file; DO NOT EDIT. */"));
+        assert!(header.contains("#define CONFIG_LED 1"));
+        assert!(!header.contains("CONFIG_DEBUG"));
+        assert!(header.trim_end().ends_with("#endif /* __AUTOCONF_H__ */"));
+    }
+
+    // 覆盖 bool / tristate / int / hex / string 各类取值的 .config
+    const SAMPLE_CONFIG: &str = "\
+#
+# Automatically generated file; DO NOT EDIT.
+# ECOS Configuration
+#
+
+#
+# Drivers
+#
+CONFIG_UART=y
+# CONFIG_SPI is not set
+CONFIG_I2C=m
+CONFIG_UART_BAUD=115200
+CONFIG_TICK_OFFSET=-1
+CONFIG_LOAD_ADDR=0x30000000
+CONFIG_BOARD_NAME=\"ecos-c1\"
+CONFIG_EMPTY_STR=\"\"
+CONFIG_BANNER=\"say \\\"hi\\\"\"
+# end of Drivers
+";
+
+    #[test]
+    fn auto_conf_drops_comments_and_unset_options() {
+        // 手写的期望值：只保留已设置的 CONFIG_X=value，取值原样输出
+        let expected = "\
+#
+# Automatically generated file; DO NOT EDIT.
+#
+CONFIG_UART=y
+CONFIG_I2C=m
+CONFIG_UART_BAUD=115200
+CONFIG_TICK_OFFSET=-1
+CONFIG_LOAD_ADDR=0x30000000
+CONFIG_BOARD_NAME=\"ecos-c1\"
+CONFIG_EMPTY_STR=\"\"
+CONFIG_BANNER=\"say \\\"hi\\\"\"
+";
+        assert_eq!(config_to_auto_conf(SAMPLE_CONFIG), expected);
+    }
+
+    #[test]
+    fn autoconf_h_defines_every_set_option() {
+        // 手写的期望值：每个已设置的选项一个 #define，m 变为 X_MODULE
+        let expected = [
+            "#define CONFIG_UART 1",
+            "#define CONFIG_I2C_MODULE 1",
+            "#define CONFIG_UART_BAUD 115200",
+            "#define CONFIG_TICK_OFFSET -1",
+            "#define CONFIG_LOAD_ADDR 0x30000000",
+            "#define CONFIG_BOARD_NAME \"ecos-c1\"",
+            "#define CONFIG_EMPTY_STR \"\"",
+            "#define CONFIG_BANNER \"say \\\"hi\\\"\"",
+        ];
+        let defines: Vec<String> = auto_conf_to_header(&config_to_auto_conf(SAMPLE_CONFIG))
+            .lines()
+            .filter(|line| line.starts_with("#define CONFIG_"))
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(defines, expected);
+
+        // 直接由 .config 生成时结果一致，未设置的项不会出现
+        let header = auto_conf_to_header(SAMPLE_CONFIG);
+        assert!(!header.contains("CONFIG_SPI"));
+        assert_eq!(header_lines(SAMPLE_CONFIG), defines);
+    }
+
+    #[test]
+    fn merge_keeps_options_missing_from_fragments() {
+        let existing = "# ECOS Configuration\n\
+                        CONFIG_UART=y\n\
+                        CONFIG_UART_BAUD=9600\n\
+                        # CONFIG_SPI is not set\n\
+                        CONFIG_BOARD_NAME=\"ecos-c1\"\n";
+        let fragments = "# CONFIG_UART_* options\n\
+                         CONFIG_UART_BAUD=115200\n\
+                         CONFIG_SPI=y\n\
+                         CONFIG_I2C=y\n";
+        assert_eq!(
+            merge_fragments(existing, fragments),
+            "# ECOS Configuration\n\
+             CONFIG_UART=y\n\
+             CONFIG_UART_BAUD=115200\n\
+             CONFIG_SPI=y\n\
+             CONFIG_BOARD_NAME=\"ecos-c1\"\n\
+             CONFIG_I2C=y\n"
+        );
+    }
+
+    #[test]
+    fn split_prefix_matches_whole_segments() {
+        assert!(option_has_prefix("UART_BAUD", "UART"));
+        assert!(option_has_prefix("UART_BAUD", "UART_BAUD"));
+        assert!(option_has_prefix("UART_BAUD_DIV", "UART_BAUD"));
+        assert!(!option_has_prefix("UART_BAUD", "UAR"));
+        assert!(!option_has_prefix("UARTX", "UART"));
+        assert_eq!(config_name("# CONFIG_SPI is not set"), Some("SPI"));
     }
 }
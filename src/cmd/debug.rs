@@ -0,0 +1,207 @@
+use crate::cmd::Command;
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command as StdCommand, Stdio};
+use std::time::{Duration, Instant};
+
+const DEFAULT_GDB: &str = "riscv64-unknown-elf-gdb";
+const DEFAULT_OPENOCD: &str = "openocd";
+
+// OpenOCD 默认端口
+const GDB_PORT: u16 = 3333;
+const TELNET_PORT: u16 = 4444;
+
+// 等待 OpenOCD 启动的最长时间
+const OPENOCD_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Args)]
+pub struct DebugCommand {
+    /// OpenOCD configuration file for the board/probe
+    /// (defaults to ecos_openocd_config in Cargo.toml)
+    #[arg(long, value_name = "FILE")]
+    openocd_config: Option<PathBuf>,
+
+    /// ELF file to debug (defaults to the most recently built debug/release ELF)
+    #[arg(long, value_name = "FILE")]
+    elf: Option<PathBuf>,
+
+    /// GDB executable
+    #[arg(long, value_name = "PATH", default_value = DEFAULT_GDB)]
+    gdb: String,
+}
+
+/// 退出时（包括出错返回）终止 OpenOCD
+struct OpenOcd(Child);
+
+impl Drop for OpenOcd {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+        println!("{} OpenOCD stopped", style("🛑").dim());
+    }
+}
+
+impl Command for DebugCommand {
+    fn execute(&self) -> Result<()> {
+        let project_root = crate::cmd::find_project_root()?;
+
+        let elf = match &self.elf {
+            Some(elf) => project_root.join(elf),
+            None => last_built_elf(&project_root)?,
+        };
+        if !elf.exists() {
+            return Err(anyhow::anyhow!("ELF file not found: {}", elf.display()));
+        }
+
+        let openocd_config = self
+            .openocd_config
+            .clone()
+            .or_else(|| {
+                crate::cmd::ecos_metadata_str(&project_root, "ecos_openocd_config")
+                    .map(PathBuf::from)
+            })
+            .map(|config| project_root.join(config))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No OpenOCD configuration.\n\
+                     \nOptions:\n\
+                     1. Run 'cargo ecos debug --openocd-config <file>'\n\
+                     2. Add to Cargo.toml:\n\
+                        [package.metadata.ecos]\n\
+                        ecos_openocd_config = \"openocd.cfg\""
+                )
+            })?;
+
+        for tool in [DEFAULT_OPENOCD, self.gdb.as_str()] {
+            if which::which(tool).is_err() {
+                return Err(anyhow::anyhow!("Tool '{}' not found in PATH", tool));
+            }
+        }
+
+        let build_dir = project_root.join("build");
+        std::fs::create_dir_all(&build_dir)?;
+        let log_path = build_dir.join("openocd.log");
+        let gdbinit = build_dir.join("debug.gdbinit");
+        std::fs::write(&gdbinit, gdbinit_content(&elf))?;
+
+        println!(
+            "{} Starting OpenOCD with {}",
+            style("🔌").cyan(),
+            style(openocd_config.display()).dim()
+        );
+        let openocd = start_openocd(&openocd_config, &log_path)?;
+        let mut openocd = OpenOcd(openocd);
+        wait_for_openocd(&mut openocd.0, &log_path)?;
+
+        // Ctrl-C 在 GDB 中用于中断目标，不能让它结束 cargo-ecos
+        ctrlc::set_handler(|| {})
+            .map_err(|e| anyhow::anyhow!("Failed to install Ctrl-C handler: {}", e))?;
+
+        println!(
+            "{} Debugging {} (OpenOCD log: {})",
+            style("🐞").cyan(),
+            style(elf.display()).bold(),
+            style(log_path.display()).dim()
+        );
+        let status = StdCommand::new(&self.gdb)
+            .arg("-q")
+            .arg("-x")
+            .arg(&gdbinit)
+            .current_dir(&project_root)
+            .status()?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("{} exited with {}", self.gdb, status));
+        }
+        Ok(())
+    }
+}
+
+/// target/<triple>/{debug,release}/<name> 中最近构建的一个
+fn last_built_elf(project_root: &Path) -> Result<PathBuf> {
+    let project_name = crate::cmd::extract_project_name(project_root)?;
+    let target = crate::cmd::build::target_dir(project_root).join(crate::cmd::build::TARGET_TRIPLE);
+
+    ["debug", "release"]
+        .iter()
+        .map(|profile| target.join(profile).join(&project_name))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No ELF found in {}. Run 'cargo ecos build' first or pass --elf.",
+                target.display()
+            )
+        })
+}
+
+/// 载入 ELF、连接 OpenOCD 并停在 main
+fn gdbinit_content(elf: &Path) -> String {
+    format!(
+        "file \"{}\"\n\
+         target extended-remote localhost:{}\n\
+         monitor reset halt\n\
+         load\n\
+         break main\n\
+         continue\n",
+        elf.display(),
+        GDB_PORT
+    )
+}
+
+fn start_openocd(config: &Path, log_path: &Path) -> Result<Child> {
+    let log = std::fs::File::create(log_path)?;
+    let mut command = StdCommand::new(DEFAULT_OPENOCD);
+    command
+        .arg("-f")
+        .arg(config)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    // 放到单独的进程组，终端中的 Ctrl-C 只发给 GDB
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    command
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", DEFAULT_OPENOCD, e))
+}
+
+/// 等待 OpenOCD 的 telnet 端口可以连接
+fn wait_for_openocd(openocd: &mut Child, log_path: &Path) -> Result<()> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], TELNET_PORT));
+    let start = Instant::now();
+
+    loop {
+        if let Some(status) = openocd.try_wait()? {
+            return Err(anyhow::anyhow!(
+                "OpenOCD exited with {} before it was ready, see {}",
+                status,
+                log_path.display()
+            ));
+        }
+        if TcpStream::connect_timeout(&addr, Duration::from_millis(200)).is_ok() {
+            return Ok(());
+        }
+        if start.elapsed() >= OPENOCD_STARTUP_TIMEOUT {
+            return Err(anyhow::anyhow!(
+                "OpenOCD did not open port {} within {}s, see {}",
+                TELNET_PORT,
+                OPENOCD_STARTUP_TIMEOUT.as_secs(),
+                log_path.display()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::cmd::Command;
+use crate::cmd::build::TARGET_TRIPLE;
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use console::style;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+
+// 需要的 RISC-V 工具链
+pub(crate) const TOOLCHAIN_TOOLS: [&str; 3] = [
+    "riscv64-unknown-elf-gcc",
+    "riscv64-unknown-elf-objcopy",
+    "riscv64-unknown-elf-objdump",
+];
+
+// 常见的 SDK 安装位置（相对 home 目录或绝对路径）
+const SDK_CANDIDATES: [&str; 4] = [
+    "~/embedded-sdk",
+    "~/ecos-sdk",
+    "/opt/embedded-sdk",
+    "/opt/ecos-sdk",
+];
+
+// 常见的 RISC-V 工具链 bin 目录
+const TOOLCHAIN_CANDIDATES: [&str; 4] = [
+    "/opt/riscv/bin",
+    "/opt/riscv64-unknown-elf/bin",
+    "~/riscv/bin",
+    "/usr/local/riscv/bin",
+];
+
+#[derive(Args)]
+pub struct DoctorCommand {
+    /// Write a script that sets up missing environment variables for the given shell
+    #[arg(long, value_enum, value_name = "SHELL")]
+    fix_env: Option<Shell>,
+
+    /// Print the check results as a JSON array instead of a report
+    #[arg(long, conflicts_with = "fix_env")]
+    json: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// 单项检查结果
+struct Check {
+    name: String,
+    status: CheckStatus,
+    message: String,
+    fix: Option<String>,
+}
+
+impl CheckStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+impl Check {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "check": self.name,
+            "status": self.status.as_str(),
+            "message": self.message,
+            "fix": self.fix,
+        })
+    }
+}
+
+impl Command for DoctorCommand {
+    fn execute(&self) -> Result<()> {
+        if self.json {
+            return print_json(&run_checks());
+        }
+
+        println!("{} Checking ECOS environment...", style("🩺").cyan());
+
+        let checks = run_checks();
+        for check in &checks {
+            let icon = match check.status {
+                CheckStatus::Pass => style("✅").green(),
+                CheckStatus::Warn => style("⚠️").yellow(),
+                CheckStatus::Fail => style("❌").red(),
+            };
+            println!(
+                "  {} {:<30} {}",
+                icon,
+                check.name,
+                style(&check.message).dim()
+            );
+            if check.status != CheckStatus::Pass
+                && let Some(fix) = &check.fix
+            {
+                println!("     {} {}", style("fix:").cyan(), fix);
+            }
+        }
+
+        if let Some(shell) = self.fix_env {
+            write_env_script(shell)?;
+        }
+
+        let failed = checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .count();
+        if failed > 0 {
+            return Err(anyhow::anyhow!("{} check(s) failed", failed));
+        }
+
+        println!("✅ Environment looks good!");
+        Ok(())
+    }
+}
+
+fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    checks.push(match crate::cmd::check_sdk_home() {
+        Ok(path) => Check {
+            name: "ECOS_SDK_HOME".to_string(),
+            status: CheckStatus::Pass,
+            message: path,
+            fix: None,
+        },
+        Err(e) => Check {
+            name: "ECOS_SDK_HOME".to_string(),
+            status: CheckStatus::Fail,
+            message: e.to_string().lines().next().unwrap_or_default().to_string(),
+            fix: Some("export ECOS_SDK_HOME=/path/to/embedded-sdk".to_string()),
+        },
+    });
+
+    for tool in TOOLCHAIN_TOOLS {
+        let found = tool_in_path(tool);
+        checks.push(Check {
+            name: tool.to_string(),
+            status: if found {
+                CheckStatus::Pass
+            } else {
+                CheckStatus::Fail
+            },
+            message: if found { "found" } else { "not found in PATH" }.to_string(),
+            fix: Some("Install the RISC-V GNU toolchain and add its bin/ to PATH".to_string()),
+        });
+    }
+
+    let target_installed = StdCommand::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .ok()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.trim() == TARGET_TRIPLE)
+        });
+    checks.push(match target_installed {
+        Some(true) => Check {
+            name: TARGET_TRIPLE.to_string(),
+            status: CheckStatus::Pass,
+            message: "installed".to_string(),
+            fix: None,
+        },
+        Some(false) => Check {
+            name: TARGET_TRIPLE.to_string(),
+            status: CheckStatus::Fail,
+            message: "not installed".to_string(),
+            fix: Some(format!("rustup target add {}", TARGET_TRIPLE)),
+        },
+        None => Check {
+            name: TARGET_TRIPLE.to_string(),
+            status: CheckStatus::Warn,
+            message: "rustup not found, cannot verify".to_string(),
+            fix: Some("Install rustup from https://rustup.rs".to_string()),
+        },
+    });
+
+    checks
+}
+
+/// --json：输出检查结果数组，有失败项时以非零状态退出
+fn print_json(checks: &[Check]) -> Result<()> {
+    let results: Vec<serde_json::Value> = checks.iter().map(Check::to_json).collect();
+    println!("{}", serde_json::to_string_pretty(&results)?);
+
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} check(s) failed", failed));
+    }
+    Ok(())
+}
+
+fn tool_in_path(tool: &str) -> bool {
+    which::which(tool).is_ok()
+}
+
+/// 展开 ~ 开头的路径
+fn expand_home(path: &str) -> Option<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+/// 在常见位置中查找第一个存在的目录
+fn find_existing(candidates: &[&str], check: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    candidates
+        .iter()
+        .filter_map(|candidate| expand_home(candidate))
+        .find(|path| check(path))
+}
+
+/// 生成设置环境变量的脚本
+fn write_env_script(shell: Shell) -> Result<()> {
+    let sdk_home = std::env::var("ECOS_SDK_HOME").ok().or_else(|| {
+        find_existing(&SDK_CANDIDATES, |p| p.join("tools/kconfig").exists())
+            .map(|p| p.display().to_string())
+    });
+
+    let toolchain_bin = if TOOLCHAIN_TOOLS.iter().all(|tool| tool_in_path(tool)) {
+        None
+    } else {
+        find_existing(&TOOLCHAIN_CANDIDATES, |p| {
+            p.join("riscv64-unknown-elf-gcc").exists()
+        })
+        .map(|p| p.display().to_string())
+    };
+
+    let sdk_value = sdk_home
+        .clone()
+        .unwrap_or_else(|| "/path/to/embedded-sdk".to_string());
+
+    let (file_name, content, source_hint) = match shell {
+        Shell::Bash | Shell::Zsh => {
+            let mut content = String::from("#!/bin/sh\n# Generated by cargo-ecos doctor\n");
+            content.push_str(&format!("export ECOS_SDK_HOME=\"{}\"\n", sdk_value));
+            if let Some(bin) = &toolchain_bin {
+                content.push_str(&format!("export PATH=\"{}:$PATH\"\n", bin));
+            }
+            ("ecos-env.sh", content, "source ./ecos-env.sh")
+        }
+        Shell::Fish => {
+            let mut content = String::from("# Generated by cargo-ecos doctor\n");
+            content.push_str(&format!("set -gx ECOS_SDK_HOME \"{}\"\n", sdk_value));
+            if let Some(bin) = &toolchain_bin {
+                content.push_str(&format!("fish_add_path \"{}\"\n", bin));
+            }
+            ("ecos-env.fish", content, "source ./ecos-env.fish")
+        }
+        Shell::Powershell => {
+            let mut content = String::from("# Generated by cargo-ecos doctor\n");
+            content.push_str(&format!("$env:ECOS_SDK_HOME = \"{}\"\n", sdk_value));
+            if let Some(bin) = &toolchain_bin {
+                content.push_str(&format!("$env:PATH = \"{};$env:PATH\"\n", bin));
+            }
+            ("ecos-env.ps1", content, ". .\\ecos-env.ps1")
+        }
+    };
+
+    std::fs::write(file_name, content)?;
+
+    println!(
+        "\n{} Wrote environment script: {}",
+        style("📝").cyan(),
+        style(file_name).bold()
+    );
+    if sdk_home.is_none() {
+        println!(
+            "  {} ECOS SDK not found in common locations, edit ECOS_SDK_HOME in the script",
+            style("⚠️").yellow()
+        );
+    }
+    println!("  To load it into your shell, run:");
+    println!("  {}", style(format!("> {}", source_hint)).dim());
+
+    Ok(())
+}
@@ -1,12 +1,37 @@
 use crate::cmd::Command;
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use console::style;
 use humansize::{DECIMAL, format_size};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command as StdCommand, Stdio};
 
+// ELF 文件头
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+
+// 默认的模拟器命令，可通过 ecos_simulator_cmd 覆盖
+const DEFAULT_SIMULATOR: &str = "ecos-sim";
+const SIMULATOR_DOCS_URL: &str = "https://github.com/ECOS-C1-SDK4Heke/cargo-ecos#simulator";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OnError {
+    /// Swallow the error and exit successfully
+    Ignore,
+    /// Exit with an error (default)
+    Abort,
+    /// Retry the copy up to --max-retries times
+    Retry,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FlashMethod {
+    /// Copy to the mounted flash path (default)
+    Copy,
+    /// Upload with scp to ecos_scp_user@ecos_scp_host:ecos_scp_path
+    Scp,
+}
+
 #[derive(Args)]
 pub struct FlashCommand {
     /// Safe mode: only flash if .bin exists, don't auto build else
@@ -29,6 +54,79 @@ pub struct FlashCommand {
     #[arg(short = 'r', long)]
     release: bool,
 
+    /// Copy firmware to a temp directory and launch the simulator instead of flashing
+    #[arg(long)]
+    simulate: bool,
+
+    /// What to do when copying the firmware fails
+    #[arg(long, value_enum, default_value_t = OnError::Abort)]
+    on_error: OnError,
+
+    /// Maximum number of retries for --on-error retry
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    max_retries: u32,
+
+    /// Shell command to run in the project root before flashing
+    /// (defaults to ecos_pre_flash_cmd in Cargo.toml)
+    #[arg(long, value_name = "CMD")]
+    pre_flash_cmd: Option<String>,
+
+    /// Shell command to run in the project root after flashing
+    /// (defaults to ecos_post_flash_cmd in Cargo.toml)
+    #[arg(long, value_name = "CMD")]
+    post_flash_cmd: Option<String>,
+
+    /// Read the firmware back after copying and compare it with the source
+    #[arg(long)]
+    verify: bool,
+
+    /// On retry, keep the part of the destination that already matches the source
+    /// (compared in 64 KiB chunks) and only write the remaining bytes
+    #[arg(long)]
+    resume: bool,
+
+    /// Write the firmware to <destination>.tmp and rename it over the target, so a
+    /// power loss never leaves a half-written file (falls back to a direct copy
+    /// with a warning if the filesystem cannot rename)
+    #[arg(long, conflicts_with_all = ["resume", "simulate"])]
+    atomic: bool,
+
+    /// Throttle the copy to BYTES_PER_SEC (accepts K/M suffixes, e.g. 512K)
+    /// and show a progress bar with the achieved rate
+    #[arg(long, value_name = "BYTES_PER_SEC", value_parser = crate::cmd::parse_size)]
+    rate_limit: Option<u64>,
+
+    /// Reset the USB device behind the flash target before flashing
+    /// (Linux: USBDEVFS_RESET, macOS: remount via diskutil)
+    #[arg(long)]
+    usb_reset: bool,
+
+    /// Path to the project's Cargo.toml (instead of searching from the current directory)
+    #[arg(long, value_name = "PATH")]
+    manifest_path: Option<PathBuf>,
+
+    /// How to transfer the firmware to the device
+    #[arg(long, value_enum, default_value_t = FlashMethod::Copy)]
+    method: FlashMethod,
+
+    /// SSH private key for --method scp (passed to scp -i)
+    #[arg(long, value_name = "PATH")]
+    ssh_key: Option<PathBuf>,
+
+    /// Also write all output of the flash (including hooks and the build) to FILE
+    #[arg(long, value_name = "FILE")]
+    with_log: Option<PathBuf>,
+
+    /// Split the .bin at byte offset ADDR (e.g. 0x10000): the first part goes to the
+    /// flash target, the second to ecos_flash_secondary_target in Cargo.toml.
+    /// Both parts are verified after copying
+    #[arg(long, value_name = "ADDR", value_parser = crate::cmd::parse_size, conflicts_with = "simulate")]
+    split_bin: Option<u64>,
+
+    /// Open the serial monitor (`cargo ecos monitor`) right after flashing
+    #[arg(long, conflicts_with = "simulate")]
+    flash_then_monitor: bool,
+
     /// Additional arguments to pass to cargo ecos build
     #[arg(last = true, allow_hyphen_values = true)]
     args: Vec<String>,
@@ -36,14 +134,27 @@ pub struct FlashCommand {
 
 impl Command for FlashCommand {
     fn execute(&self) -> Result<()> {
+        let Some(log_path) = &self.with_log else {
+            return self.flash();
+        };
+
+        let tee = OutputTee::start(log_path)?;
+        let result = self.flash();
+        tee.finish(result.as_ref().err())?;
+        result
+    }
+}
+
+impl FlashCommand {
+    fn flash(&self) -> Result<()> {
         println!("{} Flashing ECOS firmware...", style("⚡").cyan());
 
         // 找到项目根目录
-        let project_root = crate::cmd::find_project_root()?;
+        let project_root = crate::cmd::project_root_from_manifest(self.manifest_path.as_deref())?;
         std::env::set_current_dir(&project_root)?;
 
         // 获取项目名称
-        let project_name = extract_project_name(&project_root)?;
+        let project_name = crate::cmd::extract_project_name(&project_root)?;
 
         // 确定要刷写的 .bin 文件路径
         let bin_path = if let Some(custom_file) = &self.file {
@@ -100,14 +211,67 @@ impl Command for FlashCommand {
             default_bin
         };
 
-        // 获取目标路径（从配置或参数）
-        let target_path = self.get_target_path(&project_root)?;
+        // 防止误刷 ELF 或其他文件
+        self.validate_binary(&project_root, &bin_path)?;
+
+        // 模拟器模式：不需要目标设备
+        if self.simulate {
+            return self.run_simulator(&project_root, &bin_path);
+        }
+
+        // 刷写前命令，例如让设备进入升级模式
+        let pre_flash_cmd = self
+            .pre_flash_cmd
+            .clone()
+            .or_else(|| crate::cmd::ecos_metadata_str(&project_root, "ecos_pre_flash_cmd"));
+        if let Some(cmd) = &pre_flash_cmd {
+            run_flash_hook("pre-flash", cmd, &project_root)?;
+        }
+
+        let destination = match self.method {
+            FlashMethod::Scp if self.split_bin.is_some() => {
+                return Err(anyhow::anyhow!("--split-bin only supports --method copy"));
+            }
+            FlashMethod::Scp if self.atomic => {
+                return Err(anyhow::anyhow!("--atomic only supports --method copy"));
+            }
+            FlashMethod::Scp => self.scp_upload(&project_root, &bin_path)?,
+            FlashMethod::Copy if self.split_bin.is_some() => {
+                match self.flash_split(&project_root, &bin_path)? {
+                    Some(destination) => destination,
+                    None => {
+                        println!(
+                            "{} Firmware was not flashed (--on-error ignore)",
+                            style("⚠️").yellow()
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            FlashMethod::Copy => {
+                // 获取目标路径（从配置或参数）
+                let target_path = self.get_target_path(&project_root)?;
+
+                // 有些板子的 USB 存储挂载着但无响应，需要先复位
+                if self.usb_reset {
+                    usb_reset(&target_path);
+                }
+
+                // 检查目标路径是否存在并可写
+                self.check_target_path(&target_path)?;
 
-        // 检查目标路径是否存在并可写
-        self.check_target_path(&target_path)?;
+                // 执行复制操作
+                if !self.copy_bin_to_target(&bin_path, &target_path, &project_name)? {
+                    println!(
+                        "{} Firmware was not flashed (--on-error ignore)",
+                        style("⚠️").yellow()
+                    );
+                    return Ok(());
+                }
 
-        // 执行复制操作
-        self.copy_bin_to_target(&bin_path, &target_path, &project_name)?;
+                target_path.display().to_string()
+            }
+        };
 
         // 获取源文件的大小信息
         let src_metadata = fs::metadata(&bin_path)?;
@@ -116,13 +280,25 @@ impl Command for FlashCommand {
 
         println!("✅ Firmware flashed successfully!");
         println!("  From: {}", style(bin_path.display()).dim());
-        println!("  To:   {}", style(target_path.display()).dim());
+        println!("  To:   {}", style(&destination).dim());
         println!(
             "  Size: {} ({})",
             style(format_size(src_size, DECIMAL)).cyan(),
             style(format!("{} bits", src_bits)).dim()
         );
 
+        let post_flash_cmd = self
+            .post_flash_cmd
+            .clone()
+            .or_else(|| crate::cmd::ecos_metadata_str(&project_root, "ecos_post_flash_cmd"));
+        if let Some(cmd) = &post_flash_cmd {
+            run_flash_hook("post-flash", cmd, &project_root)?;
+        }
+
+        if self.flash_then_monitor {
+            crate::cmd::monitor::MonitorCommand::default().execute()?;
+        }
+
         Ok(())
     }
 }
@@ -156,6 +332,166 @@ impl FlashCommand {
         Ok(())
     }
 
+    /// --method scp：通过 scp 上传固件，返回远程目标
+    fn scp_upload(&self, project_root: &Path, bin_path: &Path) -> Result<String> {
+        let metadata = |key: &str| {
+            crate::cmd::ecos_metadata_str(project_root, key).filter(|v| !v.trim().is_empty())
+        };
+        let missing = |key: &str| {
+            anyhow::anyhow!(
+                "--method scp requires {} in Cargo.toml:\n\
+                 [package.metadata.ecos]\n\
+                 ecos_scp_host = \"192.168.1.50\"\n\
+                 ecos_scp_user = \"root\"\n\
+                 ecos_scp_path = \"/mnt/flash\"",
+                key
+            )
+        };
+
+        let host = metadata("ecos_scp_host").ok_or_else(|| missing("ecos_scp_host"))?;
+        let path = metadata("ecos_scp_path").ok_or_else(|| missing("ecos_scp_path"))?;
+        let remote_host = match metadata("ecos_scp_user") {
+            Some(user) => format!("{}@{}", user, host),
+            None => host.clone(),
+        };
+        let destination = format!("{}:{}", remote_host, path);
+
+        if !host_in_known_hosts(&host) {
+            println!(
+                "{} Host '{}' is not in known_hosts, scp may ask to confirm its key",
+                style("⚠️").yellow(),
+                host
+            );
+        }
+
+        let mut scp = StdCommand::new("scp");
+        if let Some(key) = &self.ssh_key {
+            if !key.exists() {
+                return Err(anyhow::anyhow!("SSH key not found: {}", key.display()));
+            }
+            scp.arg("-i").arg(key);
+        }
+        scp.arg(bin_path).arg(&destination);
+
+        println!(
+            "  {} Uploading to {}",
+            style("📡").cyan(),
+            style(&destination).bold()
+        );
+        let status = scp
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+            .map_err(|e| anyhow::anyhow!("Failed to run scp: {}", e))?;
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("scp to {} failed", destination));
+        }
+
+        Ok(destination)
+    }
+
+    /// 检查待刷写文件是否为 objcopy 生成的裸二进制
+    fn validate_binary(&self, project_root: &Path, path: &Path) -> Result<()> {
+        let mut header = [0u8; 4];
+        let read = {
+            use std::io::Read;
+            fs::File::open(path)?.read(&mut header)?
+        };
+
+        if read == 0 {
+            return Err(anyhow::anyhow!(
+                "Firmware file is empty: {}",
+                path.display()
+            ));
+        }
+
+        if header == ELF_MAGIC {
+            return Err(anyhow::anyhow!(
+                "{} is an ELF file, not a raw binary.\n\
+                 Flash the .bin generated by 'cargo ecos build' (build/<project>.bin) instead.",
+                path.display()
+            ));
+        }
+
+        // 可选：校验 ECOS 固件的魔数，如 ecos_bin_magic = "6f00c000"
+        if let Some(magic) = crate::cmd::ecos_metadata_str(project_root, "ecos_bin_magic") {
+            let expected = (0..magic.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(magic.get(i..i + 2)?, 16).ok())
+                .collect::<Option<Vec<u8>>>()
+                .filter(|bytes| !bytes.is_empty() && bytes.len() <= header.len())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Invalid ecos_bin_magic '{}' in Cargo.toml", magic)
+                })?;
+
+            if header[..expected.len()] != expected[..] {
+                return Err(anyhow::anyhow!(
+                    "{} does not start with the expected ECOS magic bytes ({}).\n\
+                     Make sure you are flashing the .bin file for this project.",
+                    path.display(),
+                    magic
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 复制固件到临时目录并启动模拟器
+    fn run_simulator(&self, project_root: &Path, bin_path: &Path) -> Result<()> {
+        let sim_dir = std::env::temp_dir().join("ecos_sim");
+        fs::create_dir_all(&sim_dir)?;
+
+        let sim_bin = sim_dir.join(bin_path.file_name().unwrap_or_default());
+        fs::copy(bin_path, &sim_bin)?;
+        println!(
+            "  {} Copied firmware to {}",
+            style("📋").cyan(),
+            style(sim_bin.display()).dim()
+        );
+
+        let simulator_cmd = crate::cmd::ecos_metadata_str(project_root, "ecos_simulator_cmd")
+            .unwrap_or_else(|| DEFAULT_SIMULATOR.to_string());
+        let mut parts = simulator_cmd.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("ecos_simulator_cmd is empty"))?;
+
+        println!(
+            "{} Launching simulator: {}",
+            style("🖥️").cyan(),
+            style(&simulator_cmd).bold()
+        );
+
+        let status = match StdCommand::new(program)
+            .args(parts)
+            .arg(&sim_bin)
+            .current_dir(project_root)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+        {
+            Ok(status) => status,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!(
+                    "Simulator '{}' not found in PATH\n  Install it or set ecos_simulator_cmd in [package.metadata.ecos]\n  See: {}",
+                    program,
+                    SIMULATOR_DOCS_URL
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if !status.success() {
+            return Err(anyhow::anyhow!("Simulator exited with {}", status));
+        }
+
+        Ok(())
+    }
+
     /// 获取目标路径
     fn get_target_path(&self, project_root: &Path) -> Result<PathBuf> {
         // 如果通过 --path 参数指定，使用它
@@ -191,7 +527,8 @@ impl FlashCommand {
                         ecos_flash_cmd_to = \"your_path_here\""
                 ));
             }
-            Ok(PathBuf::from(flash_path))
+            // 相对路径相对于项目根目录
+            Ok(project_root.join(flash_path))
         } else {
             Err(anyhow::anyhow!(
                 "Flash configuration not found in Cargo.toml.\n\
@@ -252,7 +589,7 @@ impl FlashCommand {
         // 检查是否可写
         #[cfg(unix)]
         {
-            use std::os::unix::fs::PermissionsExt;
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
             let metadata = fs::metadata(target_path)?;
             if metadata.permissions().mode() & 0o200 == 0 {
                 println!(
@@ -261,18 +598,128 @@ impl FlashCommand {
                     target_path.display()
                 );
             }
+
+            // 以 root 写入用户挂载的设备会绕过正常的权限检查
+            // SAFETY: geteuid 总是成功且没有副作用
+            let euid = unsafe { libc::geteuid() };
+            if metadata.uid() != euid {
+                println!(
+                    "{} Flash target is owned by uid {} but cargo-ecos runs as uid {}{}",
+                    style("⚠️").yellow(),
+                    metadata.uid(),
+                    euid,
+                    if euid == 0 { " (root)" } else { "" }
+                );
+                println!(
+                    "  Check the ownership of the mount point: {}",
+                    style(target_path.display()).dim()
+                );
+            }
+        }
+
+        // 权限位不能反映只读挂载，实际写入一个临时文件试试
+        let probe_dir = if target_path.is_dir() {
+            target_path
+        } else {
+            target_path.parent().unwrap_or(Path::new("."))
+        };
+        if let Err(e) = tempfile::tempfile_in(probe_dir) {
+            return Err(write_test_error(probe_dir, e));
+        }
+
+        // 网络文件系统的写入完成语义不可靠
+        if let Some(fs_type) = network_fs_type(target_path) {
+            println!(
+                "{} Flash target is on a network filesystem ({})",
+                style("⚠️").yellow(),
+                fs_type
+            );
+            println!(
+                "  Writes may complete lazily; use {} and make sure the target is",
+                style("--verify").cyan()
+            );
+            println!("  properly unmounted before powering the device.");
         }
 
         Ok(())
     }
 
-    /// 复制 .bin 文件到目标位置
+    /// --split-bin：在偏移处拆分固件，前半部分刷到主目标，后半部分刷到
+    /// ecos_flash_secondary_target，返回两个目标（--on-error ignore 失败时为 None）
+    fn flash_split(&self, project_root: &Path, bin_path: &Path) -> Result<Option<String>> {
+        let offset = self.split_bin.unwrap_or_default();
+        let data = fs::read(bin_path)?;
+        if offset == 0 || offset >= data.len() as u64 {
+            return Err(anyhow::anyhow!(
+                "--split-bin offset {:#x} is outside the firmware ({} bytes)",
+                offset,
+                data.len()
+            ));
+        }
+
+        let secondary = crate::cmd::ecos_metadata_str(project_root, "ecos_flash_secondary_target")
+            .filter(|path| !path.trim().is_empty())
+            .map(|path| project_root.join(path))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "--split-bin needs a secondary flash target. Add to Cargo.toml:\n\
+                     [package.metadata.ecos]\n\
+                     ecos_flash_secondary_target = \"your_path_here\""
+                )
+            })?;
+        let primary = self.get_target_path(project_root)?;
+
+        // 有些板子的 USB 存储挂载着但无响应，需要先复位
+        if self.usb_reset {
+            usb_reset(&primary);
+        }
+
+        let stem = bin_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "firmware".to_string());
+        let (first, second) = data.split_at(offset as usize);
+        let parts = [(first, primary), (second, secondary)];
+
+        let mut destinations = Vec::new();
+        for (index, (bytes, target)) in parts.iter().enumerate() {
+            let part_path = bin_path.with_file_name(format!("{}.part{}.bin", stem, index + 1));
+            fs::write(&part_path, bytes)?;
+            println!(
+                "  {} Part {}/2: {} ({})",
+                style("🧩").cyan(),
+                index + 1,
+                style(part_path.display()).dim(),
+                format_size(bytes.len() as u64, DECIMAL)
+            );
+
+            self.check_target_path(target)?;
+            let label = format!("part {}", index + 1);
+            if !self.copy_bin_to_target(&part_path, target, &label)? {
+                return Ok(None);
+            }
+            // --verify 时 copy_bin_to_target 已经校验过
+            if !self.verify {
+                let destination = if target.is_dir() {
+                    target.join(part_path.file_name().unwrap_or_default())
+                } else {
+                    target.clone()
+                };
+                verify_copy(&part_path, &destination)?;
+            }
+            destinations.push(target.display().to_string());
+        }
+
+        Ok(Some(destinations.join(", ")))
+    }
+
+    /// 复制 .bin 文件到目标位置，返回是否复制成功（--on-error ignore 时可能为 false）
     fn copy_bin_to_target(
         &self,
         bin_path: &Path,
         target_path: &Path,
         project_name: &str,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         println!("  {} Copying firmware to target...", style("📋").cyan());
 
         let destination = if target_path.is_dir() {
@@ -284,14 +731,20 @@ impl FlashCommand {
         };
 
         // 确保目标目录存在
-        if let Some(parent) = destination.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-            }
+        if let Some(parent) = destination.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent)?;
         }
 
         // 复制文件
-        fs::copy(bin_path, &destination)?;
+        if !self.copy_with_policy(bin_path, &destination)? {
+            return Ok(false);
+        }
+
+        if self.verify {
+            verify_copy(bin_path, &destination)?;
+        }
 
         println!(
             "  {} Copied {} to {}",
@@ -304,7 +757,62 @@ impl FlashCommand {
         #[cfg(unix)]
         self.sync_filesystem_if_needed(&destination)?;
 
-        Ok(())
+        Ok(true)
+    }
+
+    /// 按照 --on-error 策略复制文件
+    fn copy_with_policy(&self, bin_path: &Path, destination: &Path) -> Result<bool> {
+        let mut retries_left = self.max_retries;
+
+        loop {
+            let copy = |destination: &Path| match self.rate_limit {
+                Some(rate) => throttled_copy(bin_path, destination, rate),
+                None => fs::copy(bin_path, destination).map(|_| ()),
+            };
+            let result = if self.resume {
+                resume_copy(bin_path, destination, self.rate_limit)
+            } else if self.atomic {
+                atomic_copy(destination, copy)
+            } else {
+                copy(destination)
+            };
+            let err = match result {
+                Ok(()) => return Ok(true),
+                Err(e) => e,
+            };
+
+            match self.on_error {
+                OnError::Abort => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to copy firmware to {}: {}",
+                        destination.display(),
+                        err
+                    ));
+                }
+                OnError::Ignore => {
+                    println!("  {} Copy failed: {} (ignored)", style("⚠️").yellow(), err);
+                    return Ok(false);
+                }
+                OnError::Retry if retries_left == 0 => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to copy firmware to {} after {} retries: {}",
+                        destination.display(),
+                        self.max_retries,
+                        err
+                    ));
+                }
+                OnError::Retry => {
+                    println!(
+                        "  {} Copy failed: {} ({} retries left)",
+                        style("⚠️").yellow(),
+                        err,
+                        retries_left
+                    );
+                    retries_left -= 1;
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+            }
+        }
     }
 
     #[cfg(unix)]
@@ -329,22 +837,611 @@ impl FlashCommand {
     }
 }
 
-fn extract_project_name(project_root: &Path) -> Result<String> {
-    let cargo_toml = project_root.join("Cargo.toml");
-    let content = fs::read_to_string(&cargo_toml)?;
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("name =") {
-            let parts: Vec<&str> = trimmed.split('=').collect();
-            if parts.len() > 1 {
-                let name = parts[1].trim().trim_matches('"').trim_matches('\'');
-                return Ok(name.to_string());
+// 视为网络文件系统的类型
+const NETWORK_FS_TYPES: [&str; 8] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smbfs",
+    "smb3",
+    "afpfs",
+    "webdav",
+    "fuse.sshfs",
+];
+
+/// 检测路径所在的挂载点是否为网络文件系统，返回文件系统类型
+fn network_fs_type(path: &Path) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+
+    // (挂载点, 文件系统类型)
+    let mounts: Vec<(PathBuf, String)> = if cfg!(target_os = "linux") {
+        fs::read_to_string("/proc/mounts")
+            .ok()?
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let _device = parts.next()?;
+                let mount_point = parts.next()?.replace("\\040", " ");
+                let fs_type = parts.next()?;
+                Some((PathBuf::from(mount_point), fs_type.to_string()))
+            })
+            .collect()
+    } else if cfg!(target_os = "macos") {
+        // 格式：//user@host/share on /Volumes/share (smbfs, nodev, nosuid)
+        let output = StdCommand::new("mount").output().ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (_, rest) = line.split_once(" on ")?;
+                let (mount_point, options) = rest.rsplit_once(" (")?;
+                let fs_type = options.split([',', ')']).next()?;
+                Some((PathBuf::from(mount_point), fs_type.trim().to_string()))
+            })
+            .collect()
+    } else {
+        return None;
+    };
+
+    // 取最长匹配的挂载点
+    mounts
+        .into_iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+        .map(|(_, fs_type)| fs_type)
+        .filter(|fs_type| NETWORK_FS_TYPES.contains(&fs_type.as_str()))
+}
+
+// 复位后等待重新挂载的最长时间（秒）
+const USB_REMOUNT_TIMEOUT_SECS: u64 = 10;
+
+/// 复位刷写目标所在的 USB 设备，失败时只给出警告
+fn usb_reset(target_path: &Path) {
+    println!("  {} Resetting USB device...", style("🔌").cyan());
+
+    // 挂载点目录在卸载后仍然存在，需在复位前记下挂载点，之后检查挂载本身
+    let mount_point = mount_point_of(target_path);
+
+    match usb_reset_device(target_path) {
+        Ok(Some(device)) => {
+            println!("  {} Reset {}", style("✅").green(), style(device).dim());
+
+            let Some(mount_point) = mount_point else {
+                return;
+            };
+
+            // 先给内核时间拆除旧设备，再等待设备节点重新出现并挂载回原挂载点
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let deadline = std::time::Instant::now()
+                + std::time::Duration::from_secs(USB_REMOUNT_TIMEOUT_SECS);
+            while !is_remounted(&mount_point) {
+                if std::time::Instant::now() >= deadline {
+                    println!(
+                        "  {} {} was not remounted within {}s",
+                        style("⚠️").yellow(),
+                        mount_point.display(),
+                        USB_REMOUNT_TIMEOUT_SECS
+                    );
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        }
+        Ok(None) => {
+            println!(
+                "  {} --usb-reset is not supported on this platform ({}), skipping",
+                style("⚠️").yellow(),
+                std::env::consts::OS
+            );
+        }
+        Err(e) => {
+            println!("  {} USB reset failed: {}", style("⚠️").yellow(), e);
+        }
+    }
+}
+
+/// 路径所在的挂载点（最深的一层）
+fn mount_point_of(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    path.ancestors()
+        .find(|dir| mounted_device(dir).is_some())
+        .map(Path::to_path_buf)
+}
+
+/// 挂载点上重新挂载了设备，且设备节点存在（拔出后旧挂载可能仍留在挂载表中）
+fn is_remounted(mount_point: &Path) -> bool {
+    mounted_device(mount_point)
+        .is_some_and(|device| !device.starts_with("/dev/") || Path::new(&device).exists())
+}
+
+/// 当前挂载在该挂载点上的设备，取 /proc/mounts 中最后一条
+#[cfg(target_os = "linux")]
+fn mounted_device(mount_point: &Path) -> Option<String> {
+    let mounts = fs::read_to_string("/proc/mounts").ok()?;
+    mounts.lines().rev().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let device = parts.next()?;
+        let point = parts.next()?.replace("\\040", " ");
+        (Path::new(&point) == mount_point).then(|| device.to_string())
+    })
+}
+
+/// 当前挂载在该挂载点上的设备，解析 mount 的输出（/dev/disk4s1 on /Volumes/X (msdos, ...)）
+#[cfg(target_os = "macos")]
+fn mounted_device(mount_point: &Path) -> Option<String> {
+    let output = StdCommand::new("mount").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rev()
+        .find_map(|line| {
+            let (device, rest) = line.split_once(" on ")?;
+            let (point, _) = rest.rsplit_once(" (")?;
+            (Path::new(point) == mount_point).then(|| device.to_string())
+        })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn mounted_device(_mount_point: &Path) -> Option<String> {
+    None
+}
+
+/// 目标目录写入测试失败时的错误，只读挂载与权限不足给出重新挂载/修改权限的提示
+fn write_test_error(dir: &Path, error: std::io::Error) -> anyhow::Error {
+    match error.kind() {
+        std::io::ErrorKind::ReadOnlyFilesystem => {
+            #[cfg(target_os = "linux")]
+            let hint = match mount_source(dir) {
+                Ok(device) => format!("sudo mount -o remount,rw {}", device),
+                Err(_) => "sudo mount -o remount,rw <DEVICE>".to_string(),
+            };
+            #[cfg(target_os = "macos")]
+            let hint = format!("sudo mount -uw {}", dir.display());
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            let hint = "check the write-protect switch of the device".to_string();
+
+            anyhow::anyhow!(
+                "Flash target is on a read-only filesystem: {}\n  Remount it read-write: {}",
+                dir.display(),
+                hint
+            )
+        }
+        std::io::ErrorKind::PermissionDenied => anyhow::anyhow!(
+            "Permission denied writing to flash target: {}\n  Check the ownership of the mount point or remount it with write access \
+             (e.g. sudo mount -o remount,rw,uid=$(id -u) <DEVICE>)",
+            dir.display()
+        ),
+        _ => anyhow::anyhow!("Cannot write to flash target {}: {}", dir.display(), error),
+    }
+}
+
+/// 查找路径所在挂载点的设备节点，如 /dev/sdb1
+#[cfg(target_os = "linux")]
+fn mount_source(path: &Path) -> Result<String> {
+    let path = fs::canonicalize(path)?;
+    let mounts = fs::read_to_string("/proc/mounts")?;
+
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let device = parts.next()?;
+            let mount_point = parts.next()?.replace("\\040", " ");
+            Some((device.to_string(), PathBuf::from(mount_point)))
+        })
+        .filter(|(_, mount_point)| path.starts_with(mount_point))
+        .max_by_key(|(_, mount_point)| mount_point.as_os_str().len())
+        .map(|(device, _)| device)
+        .ok_or_else(|| anyhow::anyhow!("{} is not on a mounted device", path.display()))
+}
+
+/// Linux：挂载点 -> /sys/block -> USB 总线号/设备号 -> ioctl(USBDEVFS_RESET)
+#[cfg(target_os = "linux")]
+fn usb_reset_device(target_path: &Path) -> Result<Option<String>> {
+    use std::os::unix::io::AsRawFd;
+
+    // _IO('U', 20)
+    const USBDEVFS_RESET: libc::c_ulong = 0x5514;
+
+    let device = mount_source(target_path)?;
+    let block_name = Path::new(&device)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Unexpected mount source: {}", device))?;
+
+    // 分区（sdb1）的父目录就是整块磁盘（sdb）
+    let sys_block = fs::canonicalize(Path::new("/sys/class/block").join(block_name))?;
+    let disk = if sys_block.join("partition").exists() {
+        sys_block.parent().unwrap_or(&sys_block).to_path_buf()
+    } else {
+        sys_block
+    };
+
+    // 向上查找带有 busnum/devnum 的 USB 设备目录
+    let usb_dir = disk
+        .ancestors()
+        .find(|dir| dir.join("busnum").exists() && dir.join("devnum").exists())
+        .ok_or_else(|| anyhow::anyhow!("{} is not a USB device", device))?;
+
+    let read_num =
+        |name: &str| -> Result<u32> { Ok(fs::read_to_string(usb_dir.join(name))?.trim().parse()?) };
+    let usb_node = format!(
+        "/dev/bus/usb/{:03}/{:03}",
+        read_num("busnum")?,
+        read_num("devnum")?
+    );
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .open(&usb_node)
+        .map_err(|e| anyhow::anyhow!("Cannot open {}: {} (try running as root)", usb_node, e))?;
+
+    // SAFETY: fd 在 file 的生命周期内有效，USBDEVFS_RESET 不需要参数
+    let ret = unsafe { libc::ioctl(file.as_raw_fd(), USBDEVFS_RESET as _, 0) };
+    if ret < 0 {
+        return Err(anyhow::anyhow!(
+            "ioctl(USBDEVFS_RESET) on {} failed: {}",
+            usb_node,
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(Some(format!("{} ({})", usb_node, device)))
+}
+
+/// macOS：用 system_profiler 确认是 USB 设备，再通过 diskutil 卸载/重新挂载
+/// （没有公开的 USB 复位接口）
+#[cfg(target_os = "macos")]
+fn usb_reset_device(target_path: &Path) -> Result<Option<String>> {
+    let output = StdCommand::new("df").arg(target_path).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let device = stdout
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().next())
+        .ok_or_else(|| anyhow::anyhow!("Cannot find device for {}", target_path.display()))?;
+
+    // /dev/disk4s1 -> disk4
+    let bsd_name = device.trim_start_matches("/dev/");
+    let disk = bsd_name
+        .rfind('s')
+        .filter(|&i| i > 4)
+        .map_or(bsd_name, |i| &bsd_name[..i]);
+
+    let profile = StdCommand::new("system_profiler")
+        .arg("SPUSBDataType")
+        .output()?;
+    if !String::from_utf8_lossy(&profile.stdout).contains(&format!("BSD Name: {}", disk)) {
+        return Err(anyhow::anyhow!("{} is not a USB device", disk));
+    }
+
+    for action in ["unmountDisk", "mountDisk"] {
+        let status = StdCommand::new("diskutil")
+            .args([action, disk])
+            .stdout(Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("diskutil {} {} failed", action, disk));
+        }
+    }
+
+    Ok(Some(format!("/dev/{}", disk)))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn usb_reset_device(_target_path: &Path) -> Result<Option<String>> {
+    Ok(None)
+}
+
+// --resume 比较目标文件、--rate-limit 写入时的块大小
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 断点续写：跳过目标文件中与源文件一致的前缀块，只写入剩余部分
+fn resume_copy(source: &Path, destination: &Path, rate_limit: Option<u64>) -> std::io::Result<()> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    if destination.exists() && !fs::metadata(destination)?.is_file() {
+        return Err(std::io::Error::other(format!(
+            "--resume requires a regular file: {}",
+            destination.display()
+        )));
+    }
+
+    let data = fs::read(source)?;
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(destination)?;
+
+    // 找到第一个与源文件不一致的块
+    let mut offset = 0;
+    let mut buffer = vec![0u8; COPY_CHUNK_SIZE];
+    while offset < data.len() {
+        let expected = &data[offset..(offset + COPY_CHUNK_SIZE).min(data.len())];
+        let read = file.read(&mut buffer[..expected.len()])?;
+        if read < expected.len() || buffer[..read] != *expected {
+            break;
+        }
+        offset += expected.len();
+    }
+
+    if offset > 0 {
+        println!(
+            "  {} Resuming at {} of {}",
+            style("⏩").cyan(),
+            format_size(offset as u64, DECIMAL),
+            format_size(data.len() as u64, DECIMAL)
+        );
+    }
+
+    file.seek(SeekFrom::Start(offset as u64))?;
+    write_chunks(&mut file, &data, offset, rate_limit)?;
+    file.set_len(data.len() as u64)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// --rate-limit：限速复制整个文件
+fn throttled_copy(source: &Path, destination: &Path, rate_limit: u64) -> std::io::Result<()> {
+    let data = fs::read(source)?;
+    let mut file = fs::File::create(destination)?;
+    write_chunks(&mut file, &data, 0, Some(rate_limit))?;
+    file.sync_all()
+}
+
+/// 从 offset 开始按块写入 data；限速时每块写完后休眠，使平均速率不超过 rate_limit
+fn write_chunks(
+    file: &mut fs::File,
+    data: &[u8],
+    offset: usize,
+    rate_limit: Option<u64>,
+) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let Some(rate) = rate_limit.filter(|&rate| rate > 0) else {
+        return file.write_all(&data[offset..]);
+    };
+
+    let progress = indicatif::ProgressBar::new(data.len() as u64);
+    progress.set_style(
+        indicatif::ProgressStyle::with_template(
+            "  {bar:30.cyan/dim} {bytes}/{total_bytes} ({binary_bytes_per_sec})",
+        )
+        .unwrap_or_else(|_| indicatif::ProgressStyle::default_bar()),
+    );
+    progress.set_position(offset as u64);
+    progress.reset_eta();
+
+    let started = std::time::Instant::now();
+    let mut written = 0u64;
+    for chunk in data[offset..].chunks(COPY_CHUNK_SIZE) {
+        file.write_all(chunk)?;
+        file.flush()?;
+        written += chunk.len() as u64;
+        progress.inc(chunk.len() as u64);
+
+        // 按已写入字节数计算应耗时间，超前则等待
+        let expected = std::time::Duration::from_secs_f64(written as f64 / rate as f64);
+        if let Some(wait) = expected.checked_sub(started.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+    progress.finish();
+
+    println!(
+        "  {} Average rate: {}/s",
+        style("🐢").cyan(),
+        format_size(
+            (written as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON)) as u64,
+            DECIMAL
+        )
+    );
+    Ok(())
+}
+
+/// --atomic：先写入 <destination>.tmp 并落盘，再 rename 覆盖目标；
+/// 文件系统不支持 rename 时（部分 USB 协议栈上的 FAT32）退回直接复制
+fn atomic_copy(
+    destination: &Path,
+    copy: impl Fn(&Path) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut tmp_name = destination.as_os_str().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let written = copy(&tmp_path).and_then(|_| {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&tmp_path)?
+            .sync_all()
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    match fs::rename(&tmp_path, destination) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            println!(
+                "  {} Atomic rename not supported on {} ({}), falling back to direct copy",
+                style("⚠️").yellow(),
+                destination.display(),
+                e
+            );
+            copy(destination)
+        }
+    }
+}
+
+/// 回读目标文件并与源文件比较
+fn verify_copy(source: &Path, destination: &Path) -> Result<()> {
+    println!("  {} Verifying written firmware...", style("🔍").cyan());
+
+    if fs::read(source)? != fs::read(destination)? {
+        return Err(anyhow::anyhow!(
+            "Verification failed: {} does not match {}",
+            destination.display(),
+            source.display()
+        ));
+    }
+
+    println!("  {} Verification passed", style("✅").green());
+    Ok(())
+}
+
+/// --with-log：把 stdout/stderr 重定向到管道，由线程同时写入终端和日志文件，
+/// 子进程（构建、钩子、scp）的输出也会被记录
+#[cfg(unix)]
+struct OutputTee {
+    saved_fds: Vec<libc::c_int>,
+    threads: Vec<std::thread::JoinHandle<()>>,
+    log: std::sync::Arc<std::sync::Mutex<fs::File>>,
+}
+
+#[cfg(unix)]
+impl OutputTee {
+    const FDS: [libc::c_int; 2] = [libc::STDOUT_FILENO, libc::STDERR_FILENO];
+
+    fn start(path: &Path) -> Result<Self> {
+        use std::io::Write;
+        use std::os::fd::FromRawFd;
+
+        let file = fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("Cannot create log file {}: {}", path.display(), e))?;
+        let log = std::sync::Arc::new(std::sync::Mutex::new(file));
+
+        // 重定向后 stdout 不再是终端，先固定颜色设置
+        console::set_colors_enabled(console::colors_enabled());
+        console::set_colors_enabled_stderr(console::colors_enabled_stderr());
+        std::io::stdout().flush()?;
+
+        let mut saved_fds = Vec::new();
+        let mut threads = Vec::new();
+        for fd in Self::FDS {
+            let mut pipe = [0; 2];
+            // SAFETY: 只操作本进程的标准输出/错误与新建的管道
+            let (reader, terminal) = unsafe {
+                if libc::pipe(pipe.as_mut_ptr()) != 0 {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+                let saved = libc::dup(fd);
+                saved_fds.push(saved);
+                libc::dup2(pipe[1], fd);
+                libc::close(pipe[1]);
+                (
+                    fs::File::from_raw_fd(pipe[0]),
+                    fs::File::from_raw_fd(libc::dup(saved)),
+                )
+            };
+
+            let log = std::sync::Arc::clone(&log);
+            threads.push(std::thread::spawn(move || {
+                tee_lines(reader, terminal, &log)
+            }));
+        }
+
+        Ok(Self {
+            saved_fds,
+            threads,
+            log,
+        })
+    }
+
+    /// 恢复标准输出/错误，等待写入线程结束，并把错误信息也记入日志
+    fn finish(self, error: Option<&anyhow::Error>) -> Result<()> {
+        use std::io::Write;
+
+        std::io::stdout().flush()?;
+        std::io::stderr().flush()?;
+        // SAFETY: 恢复 start 中保存的文件描述符，之后管道写端全部关闭
+        unsafe {
+            for (fd, saved) in Self::FDS.into_iter().zip(&self.saved_fds) {
+                libc::dup2(*saved, fd);
+                libc::close(*saved);
             }
         }
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+
+        if let Some(e) = error
+            && let Ok(mut log) = self.log.lock()
+        {
+            writeln!(log, "Error: {:?}", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// 逐行转发到终端，写入日志时去掉 ANSI 颜色
+#[cfg(unix)]
+fn tee_lines(reader: fs::File, mut terminal: fs::File, log: &std::sync::Mutex<fs::File>) {
+    use std::io::{BufRead, Write};
+
+    let mut reader = std::io::BufReader::new(reader);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+        let _ = terminal.write_all(&line);
+        if let Ok(mut log) = log.lock() {
+            let text = String::from_utf8_lossy(&line);
+            let _ = log.write_all(console::strip_ansi_codes(&text).as_bytes());
+        }
+        line.clear();
+    }
+}
+
+#[cfg(not(unix))]
+struct OutputTee;
+
+#[cfg(not(unix))]
+impl OutputTee {
+    fn start(_path: &Path) -> Result<Self> {
+        Err(anyhow::anyhow!("--with-log is only supported on Unix"))
+    }
+
+    fn finish(self, _error: Option<&anyhow::Error>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 用 ssh-keygen -F 检查主机是否已在 known_hosts 中（无法检查时视为已存在）
+fn host_in_known_hosts(host: &str) -> bool {
+    StdCommand::new("ssh-keygen")
+        .args(["-F", host])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+/// 在项目根目录执行刷写前/后命令，非零退出码时中止
+fn run_flash_hook(name: &str, command: &str, project_root: &Path) -> Result<()> {
+    println!(
+        "  {} Running {} command: {}",
+        style("🪝").cyan(),
+        name,
+        style(command).dim()
+    );
+
+    let status = crate::cmd::shell_command(command)
+        .current_dir(project_root)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "{} command failed with {}: {}",
+            name,
+            status,
+            command
+        ));
     }
 
-    Err(anyhow::anyhow!(
-        "Could not extract project name from Cargo.toml"
-    ))
+    Ok(())
 }
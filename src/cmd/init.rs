@@ -1,12 +1,41 @@
 use crate::cmd::Command;
-use crate::templates::TemplateManager;
+use crate::templates::{TemplateContext, TemplateManager};
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use console::style;
 use dialoguer::{Confirm, Input, Select};
+use humansize::{DECIMAL, format_size};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-#[derive(Args)]
+// --with-examples 生成的示例（templates/common/examples/<name>.rs）
+const EXAMPLES: [&str; 2] = ["blinky", "uart_echo"];
+
+// --workspace 生成的 workspace 根 .gitignore
+const WORKSPACE_GITIGNORE: &str = "target\n*.lock\n";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CiPlatform {
+    /// GitHub Actions (.github/workflows/build.yml)
+    Github,
+    /// GitLab CI (.gitlab-ci.yml)
+    Gitlab,
+    /// Forgejo Actions (.forgejo/workflows/build.yml)
+    Forgejo,
+}
+
+impl CiPlatform {
+    /// (内置模板, 相对仓库根目录的输出路径)
+    fn files(self) -> (&'static str, &'static str) {
+        match self {
+            CiPlatform::Github => ("ci/github.yml", ".github/workflows/build.yml"),
+            CiPlatform::Gitlab => ("ci/gitlab.yml", ".gitlab-ci.yml"),
+            CiPlatform::Forgejo => ("ci/forgejo.yml", ".forgejo/workflows/build.yml"),
+        }
+    }
+}
+
+#[derive(Args, Clone)]
 pub struct InitCommand {
     /// Project directory path
     #[arg(value_name = "PATH")]
@@ -23,13 +52,119 @@ pub struct InitCommand {
     /// Where will be copy/flash to (e.g., /mnt/e or E:\\)
     #[arg(long)]
     flash: Option<String>,
+
+    /// Generate a README.md with basic project instructions
+    #[arg(long)]
+    readme: bool,
+
+    /// Package author written to Cargo.toml
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Package license written to Cargo.toml (e.g. "MIT OR Apache-2.0")
+    #[arg(long)]
+    license: Option<String>,
+
+    /// Rust edition written to Cargo.toml
+    #[arg(long)]
+    edition: Option<String>,
+
+    /// Do not initialize a git repository
+    #[arg(long)]
+    no_git: bool,
+
+    /// Read init parameters from a TOML file (CLI flags take precedence)
+    #[arg(long, value_name = "FILE")]
+    from_manifest: Option<PathBuf>,
+
+    /// Create a Cargo workspace root with the project as its first member
+    #[arg(long)]
+    workspace: bool,
+
+    /// Add the project as a new member of the enclosing Cargo workspace
+    /// (no workspace root or git repository is created)
+    #[arg(long, conflicts_with = "workspace")]
+    workspace_add: bool,
+
+    /// Package description written to Cargo.toml
+    #[arg(long, value_name = "TEXT")]
+    description: Option<String>,
+
+    /// Generate a CI configuration that builds the firmware with `cargo ecos build --release`
+    #[arg(long, value_enum, value_name = "PLATFORM")]
+    ci_config: Option<CiPlatform>,
+
+    /// Also create examples/blinky.rs and examples/uart_echo.rs as [[example]] targets
+    #[arg(long)]
+    with_examples: bool,
+
+    /// Also create a Makefile wrapper with build / flash / clean / config targets
+    /// (V=1 for verbose cargo output, PROFILE=release for release builds)
+    #[arg(long)]
+    with_makefile: bool,
+
+    /// Mark the project as publishable (publish = true) and check crates.io for
+    /// an existing crate with the same name (requires the remote-templates feature)
+    #[arg(long)]
+    publish: bool,
+
+    /// Extra template variable, referenced in template files as {{KEY}} (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_template_arg)]
+    template_args: Vec<(String, String)>,
+
+    /// Shell command to run in the new project directory once all files are created,
+    /// with PROJECT_NAME, TEMPLATE_NAME and PROJECT_DIR set in its environment
+    #[arg(long, value_name = "CMD")]
+    hook_post_init: Option<String>,
+
+    /// Do not warn about spaces or non-ASCII characters in the project path
+    #[arg(long)]
+    ignore_path_warning: bool,
+
+    /// Print the files and directories that would be created (with sizes) without
+    /// writing anything; interactive prompts still run unless --no-interactive is set
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Never prompt (for CI / scripts): PATH and --template are required, the flash
+    /// path is left unset unless --flash is given, and a non-empty directory is only
+    /// overwritten with --force
+    #[arg(long)]
+    no_interactive: bool,
 }
 
 impl Command for InitCommand {
     fn execute(&self) -> Result<()> {
+        #[cfg(feature = "check-updates")]
+        check_for_update();
+
+        match &self.from_manifest {
+            Some(manifest) => self.merged_with_manifest(manifest)?.init(),
+            None => self.init(),
+        }
+    }
+}
+
+impl InitCommand {
+    fn init(&self) -> Result<()> {
+        if self.no_interactive {
+            self.check_required_flags()?;
+        }
+
         // 获取项目目录和名称
         let (target_dir, project_name) = self.get_project_info()?;
 
+        if !self.ignore_path_warning {
+            warn_problematic_path(&target_dir);
+        }
+
+        // --workspace-add：先确认 workspace 存在且尚未包含该成员，再创建任何文件
+        let workspace_member = if self.workspace_add {
+            Some(workspace_member_path(&target_dir)?)
+        } else {
+            None
+        };
+
         // 基于 hk.cargo.toml 检测可用模板
         let available_templates = TemplateManager::list_templates();
         if available_templates.is_empty() {
@@ -60,6 +195,21 @@ impl Command for InitCommand {
         // 检查目录状态
         self.check_directory_status(&target_dir)?;
 
+        for (key, _) in &self.template_args {
+            if !TemplateManager::references_variable(&template_name, key) {
+                println!(
+                    "{} Template argument '{}' is not used by template '{}'",
+                    style("⚠️").yellow(),
+                    key,
+                    template_name
+                );
+            }
+        }
+
+        if self.publish {
+            check_crates_io_name(&project_name);
+        }
+
         // 获取 flash 设备路径
         let flash_path = if let Some(path) = &self.flash {
             // 如果通过命令行指定了，就使用它
@@ -72,25 +222,29 @@ impl Command for InitCommand {
                 "/mnt/e".to_string()
             };
 
-            let input = Input::<String>::new()
-                .with_prompt(format!(
-                    "Flash device path (press Enter to skip, e.g. {})",
-                    sample_flash
-                ))
-                .allow_empty(true)
-                .validate_with(|input: &String| {
-                    if input.is_empty() {
-                        // 允许为空，表示不配置默认路径
-                        Ok(())
-                    } else {
-                        if input.is_empty() || Path::new(input).is_absolute() {
+            let input = if self.no_interactive {
+                String::new()
+            } else {
+                Input::<String>::new()
+                    .with_prompt(format!(
+                        "Flash device path (press Enter to skip, e.g. {})",
+                        sample_flash
+                    ))
+                    .allow_empty(true)
+                    .validate_with(|input: &String| {
+                        if input.is_empty() {
+                            // 允许为空，表示不配置默认路径
                             Ok(())
                         } else {
-                            Err("Please enter an absolute path or leave empty")
+                            if input.is_empty() || Path::new(input).is_absolute() {
+                                Ok(())
+                            } else {
+                                Err("Please enter an absolute path or leave empty")
+                            }
                         }
-                    }
-                })
-                .interact()?;
+                    })
+                    .interact()?
+            };
 
             // 如果没有配置默认路径，则使用提示文本
             if input.is_empty() {
@@ -100,6 +254,13 @@ impl Command for InitCommand {
             }
         };
 
+        let author = self.resolve_author()?;
+        let context = self.template_context(&project_name, &template_name, &flash_path, &author);
+
+        if self.dry_run {
+            return self.print_dry_run(&target_dir, &project_name, &template_name, &context);
+        }
+
         // 创建项目
         println!(
             "{} Creating project '{}' with template '{}'...",
@@ -108,18 +269,73 @@ impl Command for InitCommand {
             style(&template_name).cyan()
         );
 
+        // workspace 模式下项目位于 <target_dir>/<project_name>
+        let project_dir = if self.workspace {
+            self.create_workspace_root(&target_dir, &project_name, &context)?;
+            target_dir.join(&project_name)
+        } else {
+            target_dir.clone()
+        };
+        std::fs::create_dir_all(&project_dir)?;
+
         // 使用 TemplateManager 创建项目（内部处理 hk.cargo.toml -> Cargo.toml ）
-        TemplateManager::create_project(&template_name, &target_dir, &project_name, &flash_path)?;
+        TemplateManager::create_project(&template_name, &project_dir, &context)?;
 
         // 创建必要的额外目录
-        self.create_extra_directories(&target_dir)?;
+        self.create_extra_directories(&project_dir)?;
+
+        if self.readme {
+            TemplateManager::write_common_file(
+                "README.md",
+                &project_dir.join("README.md"),
+                &context,
+            )?;
+        }
+
+        if self.with_examples {
+            self.create_examples(&project_dir, &context)?;
+        }
+
+        if self.with_makefile {
+            TemplateManager::write_common_file(
+                "Makefile",
+                &project_dir.join("Makefile"),
+                &context,
+            )?;
+        }
+
+        // CI 配置放在仓库根目录（workspace 模式下即 workspace 根）
+        if let Some(platform) = self.ci_config {
+            let (template_file, output) = platform.files();
+            TemplateManager::write_common_file(template_file, &target_dir.join(output), &context)?;
+        }
+
+        // 写入 author / license / edition
+        self.apply_package_fields(&project_dir)?;
+
+        if let Some((workspace_root, member)) = &workspace_member {
+            add_workspace_member(workspace_root, member)?;
+        }
+
+        // 在 git 初始化之前执行，钩子生成的文件也会进入初始提交
+        if let Some(command) = &self.hook_post_init {
+            run_post_init_hook(command, &project_dir, &project_name, &template_name)?;
+        }
+
+        // 检查模板所需的 Rust target 是否已安装
+        self.check_rust_target(&project_dir);
 
         // 尝试初始化 Git 仓库
-        let git_initialized = match self.init_empty_git_folder(&target_dir, &project_name) {
-            Ok(_) => true,
-            Err(e) => {
-                println!("  {}: {}", style("Git skipped").yellow().bold(), e);
-                false
+        // 加入已有 workspace 时由 workspace 所在仓库管理
+        let git_initialized = if self.no_git || self.workspace_add {
+            false
+        } else {
+            match self.init_empty_git_folder(&target_dir, &project_name) {
+                Ok(_) => true,
+                Err(e) => {
+                    println!("  {}: {}", style("Git skipped").yellow().bold(), e);
+                    false
+                }
             }
         };
 
@@ -127,9 +343,18 @@ impl Command for InitCommand {
             "✅ {} project initialized successfully!",
             style("ECOS").green()
         );
+        if self.description.as_deref().unwrap_or_default().is_empty() {
+            println!(
+                "{} No description set, 'cargo publish' requires one (edit Cargo.toml or use --description)",
+                style("💡").dim()
+            );
+        }
+        if self.workspace {
+            println!("🗂️ Workspace root: {}", style(target_dir.display()).cyan());
+        }
         println!(
             "📁 Project created at: {}",
-            style(target_dir.display()).cyan()
+            style(project_dir.display()).cyan()
         );
         println!("🎯 Target platform: {}", style(&template_name).cyan());
 
@@ -172,9 +397,367 @@ impl Command for InitCommand {
 
         Ok(())
     }
-}
 
-impl InitCommand {
+    /// 模板变量：内置变量加上 --template-args 指定的变量
+    fn template_context(
+        &self,
+        project_name: &str,
+        template_name: &str,
+        flash_path: &str,
+        author: &str,
+    ) -> TemplateContext {
+        let sdk_home =
+            std::env::var("ECOS_SDK_HOME").unwrap_or_else(|_| "/path/to/embedded-sdk".to_string());
+
+        let mut context = TemplateContext::new(project_name, flash_path);
+        context.author = author.to_string();
+        context.ecos_sdk_version = sdk_version();
+        context.extra = [
+            ("description", self.escaped_description()),
+            ("template_name", template_name.to_string()),
+            ("ecos_sdk_home", sdk_home),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .chain(self.template_args.iter().cloned())
+        .collect();
+        context
+    }
+
+    /// 作者：--author > git config user.name > 交互式输入（可为空）
+    fn resolve_author(&self) -> Result<String> {
+        if let Some(author) = &self.author {
+            return Ok(author.clone());
+        }
+
+        let git_author = std::process::Command::new("git")
+            .args(["config", "user.name"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|name| !name.is_empty());
+        if let Some(author) = git_author {
+            return Ok(author);
+        }
+
+        if self.no_interactive {
+            return Ok(String::new());
+        }
+        Ok(Input::<String>::new()
+            .with_prompt("Author (press Enter to skip)")
+            .allow_empty(true)
+            .interact()?)
+    }
+
+    /// description 写入 TOML 字符串，需要转义
+    fn escaped_description(&self) -> String {
+        self.description
+            .as_deref()
+            .unwrap_or_default()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+    }
+
+    /// --dry-run：按实际流程渲染所有文件，只输出将要创建的目录树
+    fn print_dry_run(
+        &self,
+        target_dir: &Path,
+        project_name: &str,
+        template_name: &str,
+        context: &TemplateContext,
+    ) -> Result<()> {
+        // 相对 target_dir 的路径 -> 文件大小（目录为 None）
+        let mut entries: BTreeMap<PathBuf, Option<u64>> = BTreeMap::new();
+        let project_dir = if self.workspace {
+            PathBuf::from(project_name)
+        } else {
+            PathBuf::new()
+        };
+
+        if self.workspace {
+            add_dry_run_entry(
+                &mut entries,
+                PathBuf::from("Cargo.toml"),
+                Some(workspace_cargo_toml(project_name).len()),
+            );
+            if !target_dir.join(".gitignore").exists() {
+                add_dry_run_entry(
+                    &mut entries,
+                    PathBuf::from(".gitignore"),
+                    Some(WORKSPACE_GITIGNORE.len()),
+                );
+            }
+            let readme = TemplateManager::render_common_file("workspace.README.md", context)?;
+            add_dry_run_entry(&mut entries, PathBuf::from("README.md"), Some(readme.len()));
+        }
+
+        for (path, mut content) in TemplateManager::render_project(template_name, context)? {
+            // Cargo.toml 之后还会追加 [[example]] 与 package 字段
+            if path == "Cargo.toml" {
+                if self.with_examples {
+                    content.push_str(&example_sections());
+                }
+                content = self.with_package_fields(content)?;
+            }
+            add_dry_run_entry(&mut entries, project_dir.join(path), Some(content.len()));
+        }
+
+        for dir in ["configs", "include", "build"] {
+            add_dry_run_entry(&mut entries, project_dir.join(dir), None);
+        }
+
+        if self.readme {
+            let readme = TemplateManager::render_common_file("README.md", context)?;
+            add_dry_run_entry(
+                &mut entries,
+                project_dir.join("README.md"),
+                Some(readme.len()),
+            );
+        }
+
+        if self.with_makefile {
+            let makefile = TemplateManager::render_common_file("Makefile", context)?;
+            add_dry_run_entry(
+                &mut entries,
+                project_dir.join("Makefile"),
+                Some(makefile.len()),
+            );
+        }
+
+        if self.with_examples {
+            for example in EXAMPLES {
+                let file_name = format!("examples/{}.rs", example);
+                let content = TemplateManager::render_common_file(&file_name, context)?;
+                add_dry_run_entry(
+                    &mut entries,
+                    project_dir.join(file_name),
+                    Some(content.len()),
+                );
+            }
+        }
+
+        if let Some(platform) = self.ci_config {
+            let (template_file, output) = platform.files();
+            let content = TemplateManager::render_common_file(template_file, context)?;
+            add_dry_run_entry(&mut entries, PathBuf::from(output), Some(content.len()));
+        }
+
+        if !self.no_git && !target_dir.join(".git").exists() {
+            add_dry_run_entry(&mut entries, PathBuf::from(".git"), None);
+        }
+
+        println!(
+            "{} Dry run: project '{}' with template '{}' would create:",
+            style("📋").cyan(),
+            style(project_name).bold(),
+            style(template_name).cyan()
+        );
+        println!("  {}/", style(target_dir.display()).bold());
+        print_dry_run_tree(&entries, target_dir, Path::new(""), "  ");
+
+        let files: Vec<u64> = entries.values().filter_map(|size| *size).collect();
+        println!(
+            "  {} file(s), {} director(ies), {} total",
+            files.len(),
+            entries.len() - files.len(),
+            style(format_size(files.iter().sum::<u64>(), DECIMAL)).cyan()
+        );
+        println!("{} Nothing was written (--dry-run)", style("💡").dim());
+
+        Ok(())
+    }
+
+    /// 创建示例程序，并在 Cargo.toml 中登记为 [[example]]
+    fn create_examples(&self, project_dir: &Path, context: &TemplateContext) -> Result<()> {
+        for example in EXAMPLES {
+            let file_name = format!("examples/{}.rs", example);
+            TemplateManager::write_common_file(&file_name, &project_dir.join(&file_name), context)?;
+        }
+
+        let cargo_toml = project_dir.join("Cargo.toml");
+        let mut content = std::fs::read_to_string(&cargo_toml)?;
+        content.push_str(&example_sections());
+        std::fs::write(&cargo_toml, content)?;
+
+        println!(
+            "  {} Build an example with 'cargo build --example <name>'",
+            style("💡").dim()
+        );
+        Ok(())
+    }
+
+    /// 创建 workspace 根目录的 Cargo.toml、.gitignore 与 README.md
+    fn create_workspace_root(
+        &self,
+        workspace_dir: &Path,
+        project_name: &str,
+        context: &TemplateContext,
+    ) -> Result<()> {
+        println!("{} Creating workspace root...", style("🗂️").cyan());
+
+        let cargo_toml = workspace_dir.join("Cargo.toml");
+        std::fs::write(&cargo_toml, workspace_cargo_toml(project_name))?;
+        println!("  📄 Created: {}", style(cargo_toml.display()).dim());
+
+        let gitignore = workspace_dir.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(&gitignore, WORKSPACE_GITIGNORE)?;
+            println!("  📄 Created: {}", style(gitignore.display()).dim());
+        }
+
+        TemplateManager::write_common_file(
+            "workspace.README.md",
+            &workspace_dir.join("README.md"),
+            context,
+        )?;
+
+        Ok(())
+    }
+
+    /// 读取 --from-manifest 文件，命令行参数优先
+    fn merged_with_manifest(&self, manifest_path: &Path) -> Result<InitCommand> {
+        let content = std::fs::read_to_string(manifest_path).map_err(|e| {
+            anyhow::anyhow!("Failed to read manifest {}: {}", manifest_path.display(), e)
+        })?;
+        let manifest: toml::Value = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid manifest {}: {}", manifest_path.display(), e))?;
+
+        let get_str = |key: &str| {
+            manifest
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        };
+        let get_bool = |key: &str| manifest.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let mut merged = self.clone();
+        merged.from_manifest = None;
+        merged.project_path = self
+            .project_path
+            .clone()
+            .or_else(|| get_str("project_path"));
+        merged.template = self.template.clone().or_else(|| get_str("template"));
+        merged.flash = self.flash.clone().or_else(|| get_str("flash"));
+        merged.author = self.author.clone().or_else(|| get_str("author"));
+        merged.license = self.license.clone().or_else(|| get_str("license"));
+        merged.edition = self.edition.clone().or_else(|| get_str("edition"));
+        merged.description = self.description.clone().or_else(|| get_str("description"));
+        merged.hook_post_init = self
+            .hook_post_init
+            .clone()
+            .or_else(|| get_str("hook_post_init"));
+        if merged.template_args.is_empty()
+            && let Some(args) = manifest.get("template_args").and_then(|v| v.as_table())
+        {
+            merged.template_args = args
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        toml::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect();
+        }
+        merged.ci_config = match (self.ci_config, get_str("ci_config")) {
+            (Some(platform), _) => Some(platform),
+            (None, Some(platform)) => Some(
+                CiPlatform::from_str(&platform, true)
+                    .map_err(|e| anyhow::anyhow!("Invalid ci_config in manifest: {}", e))?,
+            ),
+            (None, None) => None,
+        };
+        merged.force = self.force || get_bool("force");
+        merged.readme = self.readme || get_bool("readme");
+        merged.no_git = self.no_git || get_bool("no_git");
+        merged.workspace = self.workspace || get_bool("workspace");
+        merged.workspace_add = self.workspace_add || get_bool("workspace_add");
+        merged.with_examples = self.with_examples || get_bool("with_examples");
+        merged.with_makefile = self.with_makefile || get_bool("with_makefile");
+        merged.publish = self.publish || get_bool("publish");
+        merged.ignore_path_warning = self.ignore_path_warning || get_bool("ignore_path_warning");
+        merged.dry_run = self.dry_run || get_bool("dry_run");
+        merged.no_interactive = self.no_interactive || get_bool("no_interactive");
+
+        println!(
+            "{} Using init manifest: {}",
+            style("📜").cyan(),
+            style(manifest_path.display()).dim()
+        );
+
+        Ok(merged)
+    }
+
+    /// 将 author / license / edition 写入生成的 Cargo.toml
+    fn apply_package_fields(&self, target_dir: &Path) -> Result<()> {
+        if self
+            .package_fields()
+            .iter()
+            .all(|(_, value)| value.is_none())
+        {
+            return Ok(());
+        }
+
+        let cargo_toml = target_dir.join("Cargo.toml");
+        let content = std::fs::read_to_string(&cargo_toml)?;
+        std::fs::write(&cargo_toml, self.with_package_fields(content)?)?;
+
+        Ok(())
+    }
+
+    /// 用 toml_edit 在 Cargo.toml 内容中设置命令行指定的 package 字段，保留原有格式
+    fn with_package_fields(&self, content: String) -> Result<String> {
+        let mut doc: toml_edit::DocumentMut = content
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Failed to parse generated Cargo.toml: {}", e))?;
+        let Some(package) = doc.get_mut("package").and_then(|p| p.as_table_mut()) else {
+            return Ok(content);
+        };
+        for (key, value) in self.package_fields() {
+            if let Some(value) = value {
+                package.insert(key, toml_edit::value(value));
+            }
+        }
+        Ok(doc.to_string())
+    }
+
+    fn package_fields(&self) -> [(&'static str, Option<toml_edit::Value>); 4] {
+        [
+            (
+                "authors",
+                self.author
+                    .as_deref()
+                    .map(|a| toml_edit::Array::from_iter([a]).into()),
+            ),
+            ("license", self.license.as_deref().map(Into::into)),
+            ("edition", self.edition.as_deref().map(Into::into)),
+            ("publish", self.publish.then(|| true.into())),
+        ]
+    }
+
+    /// --no-interactive：缺少必需参数时列出所有缺少的参数
+    fn check_required_flags(&self) -> Result<()> {
+        let missing: Vec<&str> = [
+            (self.project_path.is_none(), "<PATH>"),
+            (self.template.is_none(), "--template <TEMPLATE>"),
+        ]
+        .into_iter()
+        .filter(|(missing, _)| *missing)
+        .map(|(_, flag)| flag)
+        .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Missing required arguments for --no-interactive: {}",
+                missing.join(", ")
+            ))
+        }
+    }
+
     /// 获取项目目录和名称
     fn get_project_info(&self) -> Result<(PathBuf, String)> {
         match &self.project_path {
@@ -200,18 +783,19 @@ impl InitCommand {
                 let has_parent = path.parent().map(|p| p != Path::new("")).unwrap_or(false);
 
                 // 检查父目录是否存在
-                if has_parent {
-                    if let Some(parent) = path.parent() {
-                        if !parent.exists() {
-                            if self.force {
-                                std::fs::create_dir_all(parent)?;
-                            } else {
-                                return Err(anyhow::anyhow!(
-                                    "Parent directory '{}' does not exist.\nUse -f flag to create it automatically.",
-                                    parent.display()
-                                ));
-                            }
+                if has_parent
+                    && let Some(parent) = path.parent()
+                    && !parent.exists()
+                {
+                    if self.force {
+                        if !self.dry_run {
+                            std::fs::create_dir_all(parent)?;
                         }
+                    } else {
+                        return Err(anyhow::anyhow!(
+                            "Parent directory '{}' does not exist.\nUse -f flag to create it automatically.",
+                            parent.display()
+                        ));
                     }
                 }
 
@@ -259,13 +843,20 @@ impl InitCommand {
     fn check_directory_status(&self, target_dir: &Path) -> Result<()> {
         // 目录不存在则创建
         if !target_dir.exists() {
-            std::fs::create_dir_all(target_dir)?;
+            if !self.dry_run {
+                std::fs::create_dir_all(target_dir)?;
+            }
             return Ok(());
         }
 
         if self.is_directory_non_empty(target_dir) {
             if self.force {
                 // 强制模式直接覆盖
+            } else if self.no_interactive {
+                return Err(anyhow::anyhow!(
+                    "Directory '{}' is not empty. Use --force to overwrite existing files.",
+                    target_dir.display()
+                ));
             } else {
                 let proceed = Confirm::new()
                     .with_prompt("Directory is not empty. Overwrite existing files?")
@@ -308,6 +899,47 @@ impl InitCommand {
         Ok(())
     }
 
+    /// 检查 .cargo/config.toml 中声明的 target 是否已通过 rustup 安装
+    fn check_rust_target(&self, target_dir: &Path) {
+        let Some(triple) = read_build_target(target_dir) else {
+            return;
+        };
+
+        let output = match std::process::Command::new("rustup")
+            .args(["target", "list", "--installed"])
+            .output()
+        {
+            Ok(output) if output.status.success() => output,
+            _ => {
+                println!(
+                    "  {} rustup not available, skipped target check for {}",
+                    style("⚠️").yellow(),
+                    style(&triple).cyan()
+                );
+                return;
+            }
+        };
+
+        let installed = String::from_utf8_lossy(&output.stdout);
+        if installed.lines().any(|line| line.trim() == triple) {
+            println!(
+                "  {} Rust target {} is installed",
+                style("✓").green(),
+                style(&triple).cyan()
+            );
+        } else {
+            println!(
+                "{} Rust target {} is not installed",
+                style("❌").red(),
+                style(&triple).bold()
+            );
+            println!(
+                "  Run: {}",
+                style(format!("rustup target add {}", triple)).cyan()
+            );
+        }
+    }
+
     /// 初始化空的 .git 项目
     fn init_empty_git_folder(&self, target_dir: &Path, project_name: &str) -> Result<()> {
         use anyhow::Context;
@@ -351,10 +983,10 @@ impl InitCommand {
             .current_dir(target_dir)
             .status();
 
-        if let Ok(status) = add_result {
-            if status.success() {
-                println!("    {}", style("✓ Added all files to staging").green());
-            }
+        if let Ok(status) = add_result
+            && status.success()
+        {
+            println!("    {}", style("✓ Added all files to staging").green());
         }
 
         // 创建初始提交
@@ -396,3 +1028,393 @@ impl InitCommand {
         Ok(())
     }
 }
+
+/// 查找包含 target_dir 的 workspace，返回 (workspace 根目录, 成员相对路径)
+fn workspace_member_path(target_dir: &Path) -> Result<(PathBuf, String)> {
+    let workspace_root = target_dir
+        .parent()
+        .and_then(crate::cmd::find_workspace_root)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No Cargo workspace found above {}.\n\
+                 Use --workspace to create a new one.",
+                target_dir.display()
+            )
+        })?;
+
+    // members 中统一使用 / 作为分隔符
+    let member = target_dir
+        .strip_prefix(&workspace_root)?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let content = std::fs::read_to_string(workspace_root.join("Cargo.toml"))?;
+    let manifest: toml::Value = toml::from_str(&content)?;
+    let already_member = manifest
+        .get("workspace")
+        .and_then(|ws| ws.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| members.iter().any(|m| m.as_str() == Some(member.as_str())))
+        .unwrap_or(false);
+    if already_member {
+        return Err(anyhow::anyhow!(
+            "'{}' is already a member of the workspace at {}",
+            member,
+            workspace_root.display()
+        ));
+    }
+
+    Ok((workspace_root, member))
+}
+
+/// 用 toml_edit 向 [workspace] members 追加成员，保留原有格式与注释
+fn add_workspace_member(workspace_root: &Path, member: &str) -> Result<()> {
+    let cargo_toml = workspace_root.join("Cargo.toml");
+    let content = std::fs::read_to_string(&cargo_toml)?;
+    let mut doc: toml_edit::DocumentMut = content.parse().map_err(|e| {
+        anyhow::anyhow!("Invalid workspace manifest {}: {}", cargo_toml.display(), e)
+    })?;
+
+    let workspace = doc["workspace"]
+        .as_table_like_mut()
+        .ok_or_else(|| anyhow::anyhow!("[workspace] is not a table in {}", cargo_toml.display()))?;
+    let members = workspace
+        .entry("members")
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "workspace.members is not an array in {}",
+                cargo_toml.display()
+            )
+        })?;
+    members.push(member);
+
+    std::fs::write(&cargo_toml, doc.to_string())?;
+    println!(
+        "  {} Added '{}' to workspace members in {}",
+        style("✓").green(),
+        member,
+        style(cargo_toml.display()).dim()
+    );
+    Ok(())
+}
+
+/// --hook-post-init：在项目目录中执行命令，非零退出码时报错
+fn run_post_init_hook(
+    command: &str,
+    project_dir: &Path,
+    project_name: &str,
+    template_name: &str,
+) -> Result<()> {
+    println!(
+        "  {} Running post-init hook: {}",
+        style("🪝").cyan(),
+        style(command).dim()
+    );
+
+    let status = crate::cmd::shell_command(command)
+        .current_dir(project_dir)
+        .env("PROJECT_NAME", project_name)
+        .env("TEMPLATE_NAME", template_name)
+        .env("PROJECT_DIR", project_dir)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run post-init hook: {}", e))?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "post-init hook failed with {}: {}\nThe project files were created at {}",
+            status,
+            command,
+            project_dir.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// 解析 --template-args KEY=VALUE
+fn parse_template_arg(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, val)) if !key.trim().is_empty() => Ok((key.trim().to_string(), val.to_string())),
+        _ => Err(format!("'{}' is not a KEY=VALUE pair", value)),
+    }
+}
+
+/// workspace 根的 Cargo.toml
+/// （不包含 [package.metadata.ecos]，只有成员 crate 才有）
+fn workspace_cargo_toml(project_name: &str) -> String {
+    format!(
+        "[workspace]\n\
+         resolver = \"3\"\n\
+         members = [\"{}\"]\n",
+        project_name
+    )
+}
+
+/// --with-examples 追加到 Cargo.toml 的 [[example]] 段
+fn example_sections() -> String {
+    EXAMPLES
+        .iter()
+        .map(|example| {
+            format!(
+                "\n[[example]]\nname = \"{}\"\npath = \"examples/{}.rs\"\n",
+                example, example
+            )
+        })
+        .collect()
+}
+
+/// 记录 --dry-run 将创建的路径，并补全其上级目录
+fn add_dry_run_entry(
+    entries: &mut BTreeMap<PathBuf, Option<u64>>,
+    path: PathBuf,
+    size: Option<usize>,
+) {
+    for ancestor in path.ancestors().skip(1) {
+        if ancestor.as_os_str().is_empty() {
+            break;
+        }
+        entries.entry(ancestor.to_path_buf()).or_insert(None);
+    }
+    entries.insert(path, size.map(|s| s as u64));
+}
+
+/// 以树形输出 parent 下的条目，已存在的文件标记为覆盖
+fn print_dry_run_tree(
+    entries: &BTreeMap<PathBuf, Option<u64>>,
+    target_dir: &Path,
+    parent: &Path,
+    indent: &str,
+) {
+    let children: Vec<_> = entries
+        .iter()
+        .filter(|(path, _)| path.parent() == Some(parent))
+        .collect();
+
+    for (i, (path, size)) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let branch = if last { "└── " } else { "├── " };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        match size {
+            Some(size) => {
+                let overwrite = if target_dir.join(path).is_file() {
+                    format!(" {}", style("overwrite").yellow())
+                } else {
+                    String::new()
+                };
+                println!(
+                    "{}{}{} {}{}",
+                    indent,
+                    branch,
+                    name,
+                    style(format!("({})", format_size(*size, DECIMAL))).dim(),
+                    overwrite
+                );
+            }
+            None => {
+                println!("{}{}{}/", indent, branch, style(&name).bold());
+                let child_indent = format!("{}{}", indent, if last { "    " } else { "│   " });
+                print_dry_run_tree(entries, target_dir, path, &child_indent);
+            }
+        }
+    }
+}
+
+/// ECOS_SDK_HOME 下 SDK 的版本：VERSION 文件，其次是 git describe，都没有时为 unknown
+fn sdk_version() -> String {
+    let Ok(sdk_home) = std::env::var("ECOS_SDK_HOME") else {
+        return "unknown".to_string();
+    };
+    let sdk_path = Path::new(&sdk_home);
+
+    if let Ok(version) = std::fs::read_to_string(sdk_path.join("VERSION"))
+        && !version.trim().is_empty()
+    {
+        return version.trim().to_string();
+    }
+
+    std::process::Command::new("git")
+        .args(["describe", "--tags", "--always"])
+        .current_dir(sdk_path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|version| !version.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 读取项目 .cargo/config.toml 中的 [build] target
+fn read_build_target(project_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(project_dir.join(".cargo/config.toml")).ok()?;
+    let config: toml::Value = toml::from_str(&content).ok()?;
+
+    config
+        .get("build")?
+        .get("target")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// 路径中含有空格或非 ASCII 字符时，RISC-V GCC 与 Kconfig 工具可能出错
+fn warn_problematic_path(target_dir: &Path) {
+    let path = std::env::current_dir()
+        .map(|cwd| cwd.join(target_dir))
+        .unwrap_or_else(|_| target_dir.to_path_buf());
+    let path_str = path.to_string_lossy();
+
+    let has_space = path_str.contains(' ');
+    let has_non_ascii = !path_str.is_ascii();
+    if !has_space && !has_non_ascii {
+        return;
+    }
+
+    let reason = match (has_space, has_non_ascii) {
+        (true, true) => "spaces and non-ASCII characters",
+        (true, false) => "spaces",
+        _ => "non-ASCII characters",
+    };
+    println!(
+        "{} Project path contains {}: {}",
+        style("⚠️").yellow(),
+        reason,
+        style(path.display()).dim()
+    );
+    println!("  The RISC-V toolchain and Kconfig tools may fail on such paths,");
+    println!(
+        "  consider a simpler path like ~/ecos/<name> (or pass {} to silence this).",
+        style("--ignore-path-warning").cyan()
+    );
+}
+
+/// --publish：检查 crates.io 上是否已有同名 crate，只给出警告
+#[cfg(feature = "remote-templates")]
+fn check_crates_io_name(project_name: &str) {
+    let url = format!("https://crates.io/api/v1/crates/{}", project_name);
+    let mut curl = match crate::cmd::curl_command() {
+        Ok(curl) => curl,
+        Err(e) => {
+            println!(
+                "{} Could not check crates.io for '{}': {}",
+                style("⚠️").yellow(),
+                project_name,
+                e
+            );
+            return;
+        }
+    };
+
+    // crates.io 要求请求带 User-Agent；响应体与状态码都输出到 stdout，状态码在最后一行
+    let output = curl
+        .args(["-s", "--max-time", "3", "-w", "\n%{http_code}", "-A"])
+        .arg(format!("cargo-ecos/{}", env!("CARGO_PKG_VERSION")))
+        .arg(&url)
+        .output();
+
+    match output.as_ref().map(|o| {
+        String::from_utf8_lossy(&o.stdout)
+            .lines()
+            .last()
+            .unwrap_or_default()
+            .to_string()
+    }) {
+        Ok(code) if code == "200" => {
+            println!(
+                "{} A crate named '{}' already exists on crates.io",
+                style("⚠️").yellow(),
+                style(project_name).bold()
+            );
+            println!("  Pick another name before running 'cargo publish'.");
+        }
+        Ok(code) if code == "404" => {
+            println!(
+                "  {} '{}' is available on crates.io",
+                style("✓").green(),
+                project_name
+            );
+        }
+        _ => {
+            println!(
+                "{} Could not check crates.io for '{}'",
+                style("⚠️").yellow(),
+                project_name
+            );
+        }
+    }
+}
+
+#[cfg(not(feature = "remote-templates"))]
+fn check_crates_io_name(project_name: &str) {
+    println!(
+        "{} Skipping crates.io name check for '{}' (cargo-ecos was built without the remote-templates feature)",
+        style("💡").dim(),
+        project_name
+    );
+}
+
+/// 查询 crates.io 上 cargo-ecos 的最新版本，落后超过一个主版本时给出醒目警告
+#[cfg(feature = "check-updates")]
+fn check_for_update() {
+    let current = env!("CARGO_PKG_VERSION");
+    let mut curl = match crate::cmd::curl_command() {
+        Ok(curl) => curl,
+        Err(e) => {
+            println!("{} Skipping update check: {}", style("💡").dim(), e);
+            return;
+        }
+    };
+    let output = curl
+        .args(["-s", "--max-time", "3", "-A"])
+        .arg(format!("cargo-ecos/{}", current))
+        .arg("https://crates.io/api/v1/crates/cargo-ecos")
+        .output();
+    // 网络不可用时静默跳过，不影响 init
+    let Some(latest) = output
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| serde_json::from_slice::<serde_json::Value>(&o.stdout).ok())
+        .and_then(|json| {
+            json["crate"]["max_stable_version"]
+                .as_str()
+                .map(str::to_string)
+        })
+    else {
+        return;
+    };
+
+    let parse = |version: &str| {
+        version
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<u64>>>()
+    };
+    let (Some(current_version), Some(latest_version)) = (parse(current), parse(&latest)) else {
+        return;
+    };
+
+    if latest_version[0] > current_version[0] + 1 {
+        println!(
+            "{} {}",
+            style("⚠️").yellow(),
+            style(format!(
+                "cargo-ecos {} is more than one major version behind the latest {}",
+                current, latest
+            ))
+            .yellow()
+            .bold()
+        );
+        println!("  New projects may be incompatible with recent SDKs. Upgrade with:");
+        println!("  {}", style("cargo install cargo-ecos --force").cyan());
+    } else if latest_version > current_version {
+        println!(
+            "{} cargo-ecos {} is available (installed: {})",
+            style("💡").dim(),
+            latest,
+            current
+        );
+    }
+}
@@ -1,9 +1,15 @@
 pub mod build;
 pub mod clean;
 pub mod config;
+pub mod debug;
+pub mod doctor;
 pub mod flash;
 pub mod init;
 pub mod install;
+pub mod monitor;
+pub mod project;
+pub mod status;
+pub mod template;
 
 pub trait Command {
     fn execute(&self) -> anyhow::Result<()>;
@@ -15,10 +21,8 @@ pub fn find_project_root() -> anyhow::Result<std::path::PathBuf> {
 
     loop {
         let cargo_toml = current.join("Cargo.toml");
-        if cargo_toml.exists() {
-            if is_ecos_project(&cargo_toml)? {
-                return Ok(current);
-            }
+        if cargo_toml.exists() && is_ecos_project(&cargo_toml)? {
+            return Ok(current);
         }
 
         // 到达根目录
@@ -34,24 +38,81 @@ pub fn find_project_root() -> anyhow::Result<std::path::PathBuf> {
     ))
 }
 
+// 工具函数：从 start 向上查找 workspace 根目录（包含 [workspace] 的 Cargo.toml）
+pub fn find_workspace_root(start: &std::path::Path) -> Option<std::path::PathBuf> {
+    start.ancestors().find_map(|dir| {
+        let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+        let cargo_toml: toml::Value = toml::from_str(&content).ok()?;
+        cargo_toml.get("workspace").map(|_| dir.to_path_buf())
+    })
+}
+
+// 工具函数：由 --manifest-path 确定项目根目录，未指定时回退到 find_project_root
+pub fn project_root_from_manifest(
+    manifest_path: Option<&std::path::Path>,
+) -> anyhow::Result<std::path::PathBuf> {
+    let Some(manifest_path) = manifest_path else {
+        return find_project_root();
+    };
+
+    if manifest_path
+        .file_name()
+        .map(|n| n != "Cargo.toml")
+        .unwrap_or(true)
+    {
+        return Err(anyhow::anyhow!(
+            "--manifest-path must point to a Cargo.toml file: {}",
+            manifest_path.display()
+        ));
+    }
+
+    let manifest_path = std::fs::canonicalize(manifest_path).map_err(|e| {
+        anyhow::anyhow!("Cannot access manifest {}: {}", manifest_path.display(), e)
+    })?;
+
+    if !is_ecos_project(&manifest_path)? {
+        return Err(anyhow::anyhow!(
+            "{} is not an ECOS project manifest (missing ecos_project_root = true)",
+            manifest_path.display()
+        ));
+    }
+
+    Ok(manifest_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default())
+}
+
 // 检查是否是 ECOS 项目
 pub fn is_ecos_project(cargo_toml_path: &std::path::Path) -> anyhow::Result<bool> {
     let content = std::fs::read_to_string(cargo_toml_path)?;
     let cargo_toml: toml::Value = toml::from_str(&content)?;
 
-    if let Some(package) = cargo_toml.get("package") {
-        if let Some(metadata) = package.get("metadata") {
-            if let Some(ecos) = metadata.get("ecos") {
-                if let Some(root) = ecos.get("ecos_project_root") {
-                    return Ok(root.as_bool() == Some(true));
-                }
-            }
-        }
+    if let Some(package) = cargo_toml.get("package")
+        && let Some(metadata) = package.get("metadata")
+        && let Some(ecos) = metadata.get("ecos")
+        && let Some(root) = ecos.get("ecos_project_root")
+    {
+        return Ok(root.as_bool() == Some(true));
     }
 
     Ok(false)
 }
 
+// 读取 Cargo.toml 中的 package.name
+pub fn extract_project_name(project_root: &std::path::Path) -> anyhow::Result<String> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml"))?;
+    let cargo_toml: toml::Value = toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse Cargo.toml: {}", e))?;
+
+    cargo_toml
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| anyhow::anyhow!("Could not extract project name from Cargo.toml"))
+}
+
 // 检查环境变量
 pub fn check_sdk_home() -> anyhow::Result<String> {
     match std::env::var("ECOS_SDK_HOME") {
@@ -72,3 +133,83 @@ pub fn check_sdk_home() -> anyhow::Result<String> {
         )),
     }
 }
+
+// 读取 [package.metadata.ecos] 中的配置项
+pub fn ecos_metadata_value(project_root: &std::path::Path, key: &str) -> Option<toml::Value> {
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml")).ok()?;
+    let cargo_toml: toml::Value = toml::from_str(&content).ok()?;
+
+    cargo_toml
+        .get("package")?
+        .get("metadata")?
+        .get("ecos")?
+        .get(key)
+        .cloned()
+}
+
+// 读取 [package.metadata.ecos] 中的字符串配置项
+pub fn ecos_metadata_str(project_root: &std::path::Path, key: &str) -> Option<String> {
+    ecos_metadata_value(project_root, key).and_then(|v| v.as_str().map(|s| s.to_string()))
+}
+
+// 构造通过系统 shell 执行的命令
+pub fn shell_command(command: &str) -> std::process::Command {
+    if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+// 构造 curl 命令，curl 不在 PATH 中时报错（Windows 旧版本没有自带 curl）
+pub fn curl_command() -> anyhow::Result<std::process::Command> {
+    let curl = which::which("curl").map_err(|_| {
+        anyhow::anyhow!("curl not found in PATH, install curl to enable network access")
+    })?;
+    Ok(std::process::Command::new(curl))
+}
+
+// 解析带单位的大小，如 4096、0x1000、4K、1M
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let invalid = || format!("'{}' is not a valid size (e.g. 4096, 0x1000, 4K)", value);
+
+    if let Some(hex) = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        return u64::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+
+    let (digits, multiplier) = match value.chars().last() {
+        Some('k' | 'K') => (&value[..value.len() - 1], 1024),
+        Some('m' | 'M') => (&value[..value.len() - 1], 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(invalid)
+}
+
+// 以空白分隔的 KEY=VALUE 环境变量列表
+#[derive(Clone, Debug)]
+pub struct EnvPairs(pub Vec<(String, String)>);
+
+// 解析环境变量列表，如 "KCONFIG_NOTIMESTAMP=1 FOO=bar"
+pub fn parse_env_pairs(value: &str) -> Result<EnvPairs, String> {
+    value
+        .split_whitespace()
+        .map(|pair| match pair.split_once('=') {
+            Some((key, val)) if !key.is_empty() => Ok((key.to_string(), val.to_string())),
+            _ => Err(format!("'{}' is not a KEY=VALUE pair", pair)),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(EnvPairs)
+}
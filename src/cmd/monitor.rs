@@ -0,0 +1,161 @@
+use crate::cmd::Command;
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const DEFAULT_BAUD: u32 = 115200;
+
+// 读超时，决定检查 Ctrl-C 的频率
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+// 刷写后设备重新枚举需要时间，串口打开失败时持续重试
+const OPEN_RETRY: Duration = Duration::from_secs(5);
+
+#[derive(Args, Default)]
+pub struct MonitorCommand {
+    /// Serial port, e.g. /dev/ttyUSB0 or COM3 (defaults to ecos_monitor_port in Cargo.toml)
+    #[arg(long, value_name = "PORT")]
+    port: Option<String>,
+
+    /// Baud rate (defaults to ecos_monitor_baud in Cargo.toml, then 115200)
+    #[arg(long, value_name = "BAUD")]
+    baud: Option<u32>,
+}
+
+impl Command for MonitorCommand {
+    fn execute(&self) -> Result<()> {
+        // 不在项目中也可以使用，只是没有 Cargo.toml 中的默认值
+        let project_root = crate::cmd::find_project_root().ok();
+        let metadata = |key: &str| {
+            project_root
+                .as_deref()
+                .and_then(|root| crate::cmd::ecos_metadata_value(root, key))
+        };
+
+        let Some(port_name) = self
+            .port
+            .clone()
+            .or_else(|| metadata("ecos_monitor_port").and_then(|v| v.as_str().map(str::to_string)))
+        else {
+            return Err(no_port_error());
+        };
+        let baud = self
+            .baud
+            .or_else(|| {
+                metadata("ecos_monitor_baud")
+                    .and_then(|v| v.as_integer())
+                    .and_then(|baud| u32::try_from(baud).ok())
+            })
+            .unwrap_or(DEFAULT_BAUD);
+
+        let mut port = open_port(&port_name, baud)?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let handler_stop = stop.clone();
+        ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))
+            .map_err(|e| anyhow::anyhow!("Failed to install Ctrl-C handler: {}", e))?;
+
+        println!(
+            "{} Monitoring {} at {} baud (Ctrl-C to exit)",
+            style("📟").cyan(),
+            style(&port_name).bold(),
+            baud
+        );
+
+        let mut stdout = std::io::stdout();
+        let mut buf = [0u8; 1024];
+        while !stop.load(Ordering::SeqCst) {
+            match port.read(&mut buf) {
+                Ok(0) => {}
+                Ok(n) => {
+                    stdout.write_all(&buf[..n])?;
+                    stdout.flush()?;
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::TimedOut | std::io::ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Lost connection to {}: {}", port_name, e));
+                }
+            }
+        }
+
+        println!("\n{} Monitor closed", style("👋").dim());
+        Ok(())
+    }
+}
+
+/// 打开串口；设备尚未出现时在 OPEN_RETRY 内重试
+fn open_port(port_name: &str, baud: u32) -> Result<Box<dyn serialport::SerialPort>> {
+    let start = Instant::now();
+    let mut waiting = false;
+
+    loop {
+        match serialport::new(port_name, baud)
+            .timeout(READ_TIMEOUT)
+            .open()
+        {
+            Ok(port) => return Ok(port),
+            Err(e)
+                if e.kind() == serialport::ErrorKind::NoDevice
+                    || matches!(
+                        e.kind(),
+                        serialport::ErrorKind::Io(std::io::ErrorKind::NotFound)
+                    ) =>
+            {
+                if start.elapsed() >= OPEN_RETRY {
+                    return Err(anyhow::anyhow!(
+                        "Serial port {} not found: {}",
+                        port_name,
+                        e
+                    ));
+                }
+                if !waiting {
+                    println!(
+                        "  {} Waiting for {}...",
+                        style("⏳").dim(),
+                        style(port_name).dim()
+                    );
+                    waiting = true;
+                }
+                std::thread::sleep(Duration::from_millis(250));
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Failed to open serial port {}: {}",
+                    port_name,
+                    e
+                ));
+            }
+        }
+    }
+}
+
+/// 未配置串口时的错误，列出当前可用的串口
+fn no_port_error() -> anyhow::Error {
+    let ports = serialport::available_ports()
+        .map(|ports| ports.into_iter().map(|p| p.port_name).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let available = if ports.is_empty() {
+        "none found".to_string()
+    } else {
+        ports.join(", ")
+    };
+
+    anyhow::anyhow!(
+        "No serial port configured.\n\
+         \nAvailable ports: {}\n\
+         \nOptions:\n\
+         1. Run 'cargo ecos monitor --port <port>'\n\
+         2. Add to Cargo.toml:\n\
+            [package.metadata.ecos]\n\
+            ecos_monitor_port = \"/dev/ttyUSB0\"",
+        available
+    )
+}
@@ -0,0 +1,178 @@
+use crate::cmd::Command;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use console::style;
+use humansize::{DECIMAL, format_size};
+use std::path::Path;
+use std::process::Command as StdCommand;
+
+#[derive(Args)]
+pub struct ProjectCommand {
+    #[command(subcommand)]
+    action: ProjectAction,
+}
+
+#[derive(Subcommand)]
+enum ProjectAction {
+    /// Show metadata about the current ECOS project
+    Info(ProjectInfoCommand),
+}
+
+#[derive(Args)]
+pub struct ProjectInfoCommand {
+    /// Print the project information as a JSON object
+    #[arg(long)]
+    json: bool,
+}
+
+impl Command for ProjectCommand {
+    fn execute(&self) -> Result<()> {
+        match &self.action {
+            ProjectAction::Info(cmd) => cmd.execute(),
+        }
+    }
+}
+
+impl Command for ProjectInfoCommand {
+    fn execute(&self) -> Result<()> {
+        let project_root = crate::cmd::find_project_root()?;
+        let fields = project_fields(&project_root)?;
+
+        if self.json {
+            let object: serde_json::Map<String, serde_json::Value> = fields
+                .into_iter()
+                .map(|(key, _, value)| (key.to_string(), value))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&object)?);
+            return Ok(());
+        }
+
+        println!(
+            "{} ECOS project: {}",
+            style("📦").cyan(),
+            style(project_root.display()).dim()
+        );
+        let width = fields
+            .iter()
+            .map(|(_, label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        for (_, label, value) in &fields {
+            let text = match value {
+                serde_json::Value::Null => style("-".to_string()).dim(),
+                serde_json::Value::String(s) => style(s.clone()).cyan(),
+                serde_json::Value::Array(items) if items.is_empty() => style("-".to_string()).dim(),
+                serde_json::Value::Array(items) => style(
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+                .cyan(),
+                other => style(other.to_string()).cyan(),
+            };
+            println!("  {:<width$}  {}", style(label).bold(), text, width = width);
+        }
+
+        Ok(())
+    }
+}
+
+/// 收集项目信息：(JSON 键, 表格标签, 值)
+fn project_fields(
+    project_root: &Path,
+) -> Result<Vec<(&'static str, &'static str, serde_json::Value)>> {
+    use serde_json::Value;
+
+    let content = std::fs::read_to_string(project_root.join("Cargo.toml"))?;
+    let cargo_toml: toml::Value = toml::from_str(&content)?;
+    let package = cargo_toml.get("package");
+    let package_str = |key: &str| {
+        package
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_str())
+            .map(|s| Value::String(s.to_string()))
+            .unwrap_or(Value::Null)
+    };
+    let metadata_str = |key: &str| {
+        crate::cmd::ecos_metadata_str(project_root, key)
+            .map(Value::String)
+            .unwrap_or(Value::Null)
+    };
+
+    // 与 flash 一致，只认单个路径字符串
+    let flash_targets: Vec<Value> =
+        crate::cmd::ecos_metadata_str(project_root, "ecos_flash_cmd_to")
+            .filter(|path| !path.is_empty())
+            .map(Value::String)
+            .into_iter()
+            .collect();
+
+    let project_name = crate::cmd::extract_project_name(project_root)?;
+    let bin = project_root
+        .join("build")
+        .join(format!("{}.bin", project_name));
+    let bin_metadata = std::fs::metadata(&bin).ok();
+    let last_build = bin_metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .map(|time| {
+            let time: chrono::DateTime<chrono::Local> = time.into();
+            Value::String(time.format("%Y-%m-%d %H:%M:%S").to_string())
+        })
+        .unwrap_or(Value::Null);
+    let binary_size = bin_metadata
+        .map(|m| Value::String(format_size(m.len(), DECIMAL)))
+        .unwrap_or(Value::Null);
+
+    Ok(vec![
+        ("name", "Name", package_str("name")),
+        ("version", "Version", package_str("version")),
+        ("description", "Description", package_str("description")),
+        ("template", "Template", metadata_str("ecos_template")),
+        (
+            "sdk_version",
+            "SDK version",
+            metadata_str("ecos_sdk_version"),
+        ),
+        (
+            "flash_targets",
+            "Flash targets",
+            Value::Array(flash_targets),
+        ),
+        (
+            "target",
+            "Target",
+            Value::String(crate::cmd::build::TARGET_TRIPLE.to_string()),
+        ),
+        (
+            "default_config",
+            "Default config",
+            metadata_str("ecos_default_config"),
+        ),
+        ("last_build", "Last build", last_build),
+        ("binary_size", "Binary size", binary_size),
+        ("git_status", "Git status", git_status(project_root)),
+    ])
+}
+
+/// clean / dirty，不在 git 仓库中时为 null
+fn git_status(project_root: &Path) -> serde_json::Value {
+    let in_repo = StdCommand::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(project_root)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !in_repo {
+        return serde_json::Value::Null;
+    }
+
+    let status = if crate::cmd::build::git_tree_dirty(project_root) {
+        "dirty"
+    } else {
+        "clean"
+    };
+    serde_json::Value::String(status.to_string())
+}
@@ -0,0 +1,148 @@
+use crate::cmd::Command;
+use crate::cmd::doctor::TOOLCHAIN_TOOLS;
+use anyhow::Result;
+use clap::Args;
+use console::style;
+use std::path::Path;
+
+#[derive(Args)]
+pub struct StatusCommand {}
+
+/// 状态表中的一行
+struct StatusItem {
+    name: String,
+    ok: bool,
+    // 缺失时是否导致非零退出
+    required: bool,
+    detail: String,
+}
+
+impl StatusItem {
+    fn required(name: &str, ok: bool, detail: impl Into<String>) -> Self {
+        StatusItem {
+            name: name.to_string(),
+            ok,
+            required: true,
+            detail: detail.into(),
+        }
+    }
+
+    fn optional(name: &str, ok: bool, detail: impl Into<String>) -> Self {
+        StatusItem {
+            required: false,
+            ..StatusItem::required(name, ok, detail)
+        }
+    }
+}
+
+impl Command for StatusCommand {
+    fn execute(&self) -> Result<()> {
+        println!("{} ECOS project status", style("📋").cyan());
+
+        let items = collect_status();
+        for item in &items {
+            let icon = match (item.ok, item.required) {
+                (true, _) => style("✅").green(),
+                (false, true) => style("❌").red(),
+                (false, false) => style("⚠️").yellow(),
+            };
+            let detail = if item.ok {
+                style(&item.detail).dim()
+            } else if item.required {
+                style(&item.detail).red()
+            } else {
+                style(&item.detail).yellow()
+            };
+            println!("  {} {:<30} {}", icon, item.name, detail);
+        }
+
+        let missing = items.iter().filter(|i| i.required && !i.ok).count();
+        if missing > 0 {
+            return Err(anyhow::anyhow!("{} required item(s) missing", missing));
+        }
+
+        println!("✅ Ready to build!");
+        Ok(())
+    }
+}
+
+fn collect_status() -> Vec<StatusItem> {
+    let mut items = Vec::new();
+
+    match crate::cmd::find_project_root() {
+        Ok(root) => {
+            items.push(StatusItem::required(
+                "Project root",
+                true,
+                root.display().to_string(),
+            ));
+            project_status(&root, &mut items);
+        }
+        Err(e) => items.push(StatusItem::required(
+            "Project root",
+            false,
+            e.to_string().lines().next().unwrap_or_default(),
+        )),
+    }
+
+    items.push(match crate::cmd::check_sdk_home() {
+        Ok(path) => StatusItem::required("ECOS_SDK_HOME", true, path),
+        Err(e) => StatusItem::required(
+            "ECOS_SDK_HOME",
+            false,
+            e.to_string().lines().next().unwrap_or_default(),
+        ),
+    });
+
+    for tool in TOOLCHAIN_TOOLS {
+        items.push(match which::which(tool) {
+            Ok(path) => StatusItem::required(tool, true, path.display().to_string()),
+            Err(_) => StatusItem::required(tool, false, "not found in PATH"),
+        });
+    }
+
+    items
+}
+
+/// 项目相关的检查：Cargo.toml 元数据与生成的配置头文件
+fn project_status(root: &Path, items: &mut Vec<StatusItem>) {
+    items.push(match crate::cmd::extract_project_name(root) {
+        Ok(name) => StatusItem::required("Project name", true, name),
+        Err(e) => StatusItem::required("Project name", false, e.to_string()),
+    });
+
+    let sdk_version = crate::cmd::ecos_metadata_str(root, "ecos_sdk_version");
+    items.push(StatusItem::optional(
+        "SDK version (Cargo.toml)",
+        sdk_version.is_some(),
+        sdk_version.unwrap_or_else(|| "ecos_sdk_version not set".to_string()),
+    ));
+
+    // flash 只接受单个路径字符串，其他形式在这里同样报告为无效
+    items.push(
+        match crate::cmd::ecos_metadata_value(root, "ecos_flash_cmd_to") {
+            Some(toml::Value::String(path)) if !path.is_empty() => {
+                StatusItem::optional("Flash target", true, path)
+            }
+            Some(toml::Value::String(_)) | None => {
+                StatusItem::optional("Flash target", false, "ecos_flash_cmd_to not set")
+            }
+            Some(_) => StatusItem::optional(
+                "Flash target",
+                false,
+                "ecos_flash_cmd_to must be a single path string",
+            ),
+        },
+    );
+
+    let autoconf_h = root.join("include/generated/autoconf.h");
+    items.push(if autoconf_h.exists() {
+        StatusItem::required("autoconf.h", true, "include/generated/autoconf.h")
+    } else {
+        StatusItem::required(
+            "autoconf.h",
+            false,
+            "not found, run 'cargo ecos config' first",
+        )
+    });
+}
@@ -0,0 +1,300 @@
+use crate::cmd::Command;
+use crate::templates::{TEMPLATE_MANIFEST, TemplateManager};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use console::style;
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use std::path::PathBuf;
+
+// template create 可选的额外目录（src 和 .cargo 总会创建）
+const OPTIONAL_DIRS: [&str; 3] = ["include", "examples", "docs"];
+
+const EDITIONS: [&str; 2] = ["2024", "2021"];
+
+#[derive(Args)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    action: TemplateAction,
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// Print a SHA-256 digest over all files of an embedded template
+    Checksum {
+        /// Template name (c1, c2, l3)
+        name: String,
+    },
+
+    /// Interactively scaffold a new template in the cargo-ecos source tree
+    /// (templates/<NAME>, for local development)
+    Create {
+        /// Name of the new template directory
+        name: String,
+    },
+}
+
+/// template create 收集到的回答
+struct TemplateAnswers {
+    dirs: Vec<&'static str>,
+    c_headers: bool,
+    flash_path: String,
+    edition: &'static str,
+}
+
+impl Command for TemplateCommand {
+    fn execute(&self) -> Result<()> {
+        match &self.action {
+            TemplateAction::Checksum { name } => {
+                println!("{}", TemplateManager::template_checksum(name)?);
+            }
+            TemplateAction::Create { name } => create_template(name)?,
+        }
+        Ok(())
+    }
+}
+
+/// template create：询问模板结构后写入 templates/<name>
+fn create_template(name: &str) -> Result<()> {
+    validate_template_name(name)?;
+
+    // 模板在编译时嵌入，只能加到 cargo-ecos 的 git 源码树中
+    // （cargo install 从 crates.io 安装时 CARGO_MANIFEST_DIR 指向 registry 中的只读副本）
+    let source_root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let templates_root = source_root.join("templates");
+    if !source_root.join(".git").exists() || !templates_root.is_dir() {
+        return Err(anyhow::anyhow!(
+            "cargo-ecos git checkout not found at {}.\n\
+             'template create' is meant for local development: build cargo-ecos from a git checkout.",
+            source_root.display()
+        ));
+    }
+
+    let template_dir = templates_root.join(name);
+    if template_dir.exists() {
+        return Err(anyhow::anyhow!(
+            "Template directory {} already exists",
+            template_dir.display()
+        ));
+    }
+
+    println!(
+        "{} Creating template {}",
+        style("🧩").cyan(),
+        style(name).bold()
+    );
+    let answers = ask_template_questions()?;
+
+    for (relative_path, content) in template_files(name, &answers) {
+        let path = template_dir.join(relative_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        println!("  📄 Created: {}", style(path.display()).dim());
+    }
+
+    println!(
+        "\n{} Template {} created at {}",
+        style("✅").green(),
+        style(name).bold(),
+        template_dir.display()
+    );
+    println!("\nNext steps:");
+    println!("  1. Rebuild cargo-ecos so the template gets embedded:");
+    println!("     {}", style("cargo install --path .").cyan());
+    println!("  2. Try it out:");
+    println!(
+        "     {}",
+        style(format!("cargo ecos init my_project --template {}", name)).cyan()
+    );
+
+    Ok(())
+}
+
+/// 模板名会成为目录名和 --template 的值
+fn validate_template_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+    {
+        return Err(anyhow::anyhow!(
+            "Invalid template name '{}': use lowercase letters, digits, '-' and '_'",
+            name
+        ));
+    }
+    if name == "common" {
+        return Err(anyhow::anyhow!(
+            "'common' is reserved for files shared by all templates"
+        ));
+    }
+    Ok(())
+}
+
+fn ask_template_questions() -> Result<TemplateAnswers> {
+    let selected = MultiSelect::new()
+        .with_prompt("Extra directories (src/ and .cargo/ are always created)")
+        .items(OPTIONAL_DIRS)
+        .interact()?;
+    let mut dirs: Vec<&'static str> = selected.into_iter().map(|i| OPTIONAL_DIRS[i]).collect();
+
+    let c_headers = Confirm::new()
+        .with_prompt("Include example C headers (include/)?")
+        .default(false)
+        .interact()?;
+    if c_headers && !dirs.contains(&"include") {
+        dirs.insert(0, "include");
+    }
+
+    let flash_path = Input::<String>::new()
+        .with_prompt("Flash path written into new projects (press Enter to ask at init)")
+        .allow_empty(true)
+        .interact_text()?;
+
+    let edition = EDITIONS[Select::new()
+        .with_prompt("Rust edition")
+        .items(EDITIONS)
+        .default(0)
+        .interact()?];
+
+    Ok(TemplateAnswers {
+        dirs,
+        c_headers,
+        flash_path,
+        edition,
+    })
+}
+
+/// 新模板的全部文件，返回 (相对模板目录的路径, 内容)
+fn template_files(name: &str, answers: &TemplateAnswers) -> Vec<(String, String)> {
+    let mut files = vec![
+        ("hk.cargo.toml".to_string(), hk_cargo_toml(answers)),
+        (
+            TEMPLATE_MANIFEST.to_string(),
+            template_manifest(name, answers),
+        ),
+        (
+            ".cargo/config.toml".to_string(),
+            cargo_config(crate::cmd::build::TARGET_TRIPLE),
+        ),
+        ("src/main.rs".to_string(), MAIN_RS.to_string()),
+        (".gitignore".to_string(), GITIGNORE.to_string()),
+    ];
+
+    for dir in &answers.dirs {
+        if *dir == "include" && answers.c_headers {
+            files.push((
+                "include/ecos_example.h".to_string(),
+                EXAMPLE_HEADER.to_string(),
+            ));
+        } else {
+            // 空目录不会被 include_dir 和 git 保留
+            files.push((format!("{}/.gitkeep", dir), String::new()));
+        }
+    }
+
+    files
+}
+
+/// 指定了 flash 路径时直接写入，否则保留占位符，由 init 询问
+fn hk_cargo_toml(answers: &TemplateAnswers) -> String {
+    let flash_path = if answers.flash_path.is_empty() {
+        "\"{{flash_path}}\"".to_string()
+    } else {
+        toml::Value::String(answers.flash_path.clone()).to_string()
+    };
+    format!(
+        "[package]\n\
+         name = \"{{{{project_name}}}}\"\n\
+         version = \"0.1.0\"\n\
+         description = \"{{{{description}}}}\"\n\
+         edition = \"{}\"\n\
+         \n\
+         [package.metadata.ecos]\n\
+         ecos_project_root = true\n\
+         ecos_flash_cmd_to = {}\n\
+         ecos_sdk_version = \"{{{{ecos_sdk_version}}}}\"\n\
+         \n\
+         [dependencies]\n",
+        answers.edition, flash_path
+    )
+}
+
+fn template_manifest(name: &str, answers: &TemplateAnswers) -> String {
+    let mut manifest = toml::map::Map::new();
+    manifest.insert("name".to_string(), name.into());
+    manifest.insert("edition".to_string(), answers.edition.into());
+    manifest.insert(
+        "directories".to_string(),
+        toml::Value::Array(answers.dirs.iter().map(|d| (*d).into()).collect()),
+    );
+    manifest.insert("c_headers".to_string(), answers.c_headers.into());
+
+    format!(
+        "# Describes the template for contributors, not copied into new projects\n{}",
+        toml::to_string(&manifest).unwrap_or_default()
+    )
+}
+
+/// .cargo/config.toml：与 c1 相同的链接参数，-march/-mabi 取自目标三元组
+fn cargo_config(target: &str) -> String {
+    let arch = target.split('-').next().unwrap_or(target);
+    let march = arch.replacen("riscv", "rv", 1);
+    let mabi = if arch.starts_with("riscv64") {
+        "lp64"
+    } else {
+        "ilp32"
+    };
+
+    let flags = [
+        format!("link-arg=-mabi={}", mabi),
+        format!("link-arg=-march={}", march),
+        "link-arg=-ffreestanding".to_string(),
+        "link-arg=-nostdlib".to_string(),
+        "panic=abort".to_string(),
+        "opt-level=s".to_string(),
+        "relocation-model=static".to_string(),
+        "link-arg=-Wl,--gc-sections".to_string(),
+        "link-arg=-nostartfiles".to_string(),
+    ];
+
+    let mut config = format!(
+        "[build]\ntarget = \"{0}\"\n\n[target.{0}]\nlinker = \"riscv64-unknown-elf-gcc\"\nrustflags = [\n",
+        target
+    );
+    for flag in flags {
+        config.push_str(&format!("    \"-C\", \"{}\",\n", flag));
+    }
+    config.push_str("]\n");
+    config
+}
+
+const MAIN_RS: &str = r#"#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+
+#[unsafe(no_mangle)]
+pub extern "C" fn main() -> ! {
+    // {{project_name}} entry point
+    loop {}
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
+"#;
+
+const EXAMPLE_HEADER: &str = r#"#ifndef ECOS_EXAMPLE_H
+#define ECOS_EXAMPLE_H
+
+#include <stdint.h>
+
+/* Example C API exposed to {{project_name}} */
+uint32_t ecos_example_add(uint32_t a, uint32_t b);
+
+#endif /* ECOS_EXAMPLE_H */
+"#;
+
+const GITIGNORE: &str = "target\n*.lock\noutput\n.*\n!.cargo\n!.gitignore\n!.gitkeep\nbuild\n";
@@ -7,8 +7,9 @@ use clap::{Parser, Subcommand, crate_version};
 #[allow(unused)]
 use cmd::install::{InstallCommand, UninstallCommand};
 use cmd::{
-    Command, build::BuildCommand, clean::CleanCommand, config::ConfigCommand, flash::FlashCommand,
-    init::InitCommand,
+    Command, build::BuildCommand, clean::CleanCommand, config::ConfigCommand, debug::DebugCommand,
+    doctor::DoctorCommand, flash::FlashCommand, init::InitCommand, monitor::MonitorCommand,
+    project::ProjectCommand, status::StatusCommand, template::TemplateCommand,
 };
 
 #[derive(Parser)]
@@ -30,14 +31,32 @@ enum EcosCommands {
     Config(ConfigCommand),
 
     /// Build ECOS firmware
-    Build(BuildCommand),
+    Build(Box<BuildCommand>),
 
     /// Flash firmware to target device
     Flash(FlashCommand),
 
+    /// Debug the firmware with GDB over OpenOCD
+    Debug(DebugCommand),
+
+    /// Stream serial output from the device
+    Monitor(MonitorCommand),
+
     /// Clean all build artifacts
     Clean(CleanCommand),
 
+    /// Check the development environment
+    Doctor(DoctorCommand),
+
+    /// Inspect the embedded project templates
+    Template(TemplateCommand),
+
+    /// Show information about the current project
+    Project(ProjectCommand),
+
+    /// Check that the project is ready to build
+    Status(StatusCommand),
+
     /// Install templates to system (dev
     #[cfg_attr(not(feature = "install"), doc = "")]
     #[cfg_attr(not(feature = "install"), command(hide = true))]
@@ -61,8 +80,14 @@ fn main() -> anyhow::Result<()> {
         EcosCommands::Init(cmd) => cmd.execute(),
         EcosCommands::Config(cmd) => cmd.execute(),
         EcosCommands::Build(cmd) => cmd.execute(),
+        EcosCommands::Debug(cmd) => cmd.execute(),
+        EcosCommands::Monitor(cmd) => cmd.execute(),
         EcosCommands::Clean(cmd) => cmd.execute(),
         EcosCommands::Flash(cmd) => cmd.execute(),
+        EcosCommands::Doctor(cmd) => cmd.execute(),
+        EcosCommands::Template(cmd) => cmd.execute(),
+        EcosCommands::Project(cmd) => cmd.execute(),
+        EcosCommands::Status(cmd) => cmd.execute(),
         #[cfg(feature = "install")]
         EcosCommands::Install(cmd) => cmd.execute(),
         #[cfg(feature = "install")]
@@ -1,10 +1,56 @@
 use anyhow::Result;
 use console::style;
 use include_dir::{Dir, include_dir};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::Path;
 
 static TEMPLATES_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates");
 
+// 公共模板文件目录（不含 hk.cargo.toml，不会被当作模板列出）
+const COMMON_DIR: &str = "common";
+
+// 模板描述文件（由 template create 生成），不会复制到项目中
+pub const TEMPLATE_MANIFEST: &str = "template.toml";
+
+/// 渲染模板时可用的变量，模板文件中以 {{name}} 引用
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    pub project_name: String,
+    /// 烧录路径，{{flash_path}} / {{device_path}} 会按 TOML 字符串转义
+    pub flash_path: String,
+    pub author: String,
+    pub ecos_sdk_version: String,
+    pub year: String,
+    /// 其他变量，如 description、template_name 以及 --template-args
+    pub extra: Vec<(String, String)>,
+}
+
+impl TemplateContext {
+    pub fn new(project_name: &str, flash_path: &str) -> Self {
+        Self {
+            project_name: project_name.to_string(),
+            flash_path: flash_path.to_string(),
+            year: chrono::Local::now().format("%Y").to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 变量名 -> 替换值；内置变量优先于同名的 extra 变量
+    fn variables(&self) -> HashMap<&str, &str> {
+        let mut vars: HashMap<&str, &str> = self
+            .extra
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        vars.insert("project_name", &self.project_name);
+        vars.insert("author", &self.author);
+        vars.insert("ecos_sdk_version", &self.ecos_sdk_version);
+        vars.insert("year", &self.year);
+        vars
+    }
+}
+
 #[derive(Debug)]
 pub struct TemplateManager;
 
@@ -80,19 +126,82 @@ impl TemplateManager {
     pub fn create_project(
         template_name: &str,
         project_dir: &Path,
-        project_name: &str,
-        device_path: &str,
+        context: &TemplateContext,
     ) -> Result<()> {
         let template = Self::get_template(template_name)?;
 
         println!("{} Creating project structure...", style("📁").cyan());
 
         Self::create_directory_structure(template, project_dir, "")?;
-        Self::process_template_files(template, project_dir, "", project_name, device_path)?;
+        for (relative_path, content) in Self::render_project(template_name, context)? {
+            let target_path = project_dir.join(relative_path);
+            std::fs::write(&target_path, content)?;
+            println!("  📄 Created: {}", style(target_path.display()).dim());
+        }
+
+        Ok(())
+    }
+
+    /// 渲染模板的全部文件（不写入磁盘），返回 (相对项目目录的路径, 内容)
+    pub fn render_project(
+        template_name: &str,
+        context: &TemplateContext,
+    ) -> Result<Vec<(String, String)>> {
+        let template = Self::get_template(template_name)?;
+
+        let mut files = Vec::new();
+        Self::render_template_files(template, "", context, &mut files)?;
+        Ok(files)
+    }
+
+    /// 模板目录或 common 目录中是否有文件引用了 {{name}}
+    pub fn references_variable(template_name: &str, name: &str) -> bool {
+        let placeholder = format!("{{{{{}}}}}", name);
+        [
+            TEMPLATES_DIR.get_dir(template_name),
+            TEMPLATES_DIR.get_dir(COMMON_DIR),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|dir| Self::dir_contains(dir, &placeholder))
+    }
+
+    fn dir_contains(dir: &Dir, pattern: &str) -> bool {
+        dir.files().any(|file| {
+            file.contents_utf8()
+                .is_some_and(|content| content.contains(pattern))
+        }) || dir.dirs().any(|subdir| Self::dir_contains(subdir, pattern))
+    }
+
+    /// 将 common 目录下的公共模板文件渲染到 target_path
+    pub fn write_common_file(
+        file_name: &str,
+        target_path: &Path,
+        context: &TemplateContext,
+    ) -> Result<()> {
+        let processed = Self::render_common_file(file_name, context)?;
+
+        if let Some(parent) = target_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target_path, processed)?;
 
+        println!("  📄 Created: {}", style(target_path.display()).dim());
         Ok(())
     }
 
+    /// 渲染 common 目录下的公共模板文件（不写入磁盘）
+    pub fn render_common_file(file_name: &str, context: &TemplateContext) -> Result<String> {
+        let file = TEMPLATES_DIR
+            .get_file(format!("{}/{}", COMMON_DIR, file_name))
+            .ok_or_else(|| anyhow::anyhow!("Common template file '{}' not found", file_name))?;
+
+        let content = std::str::from_utf8(file.contents())
+            .map_err(|e| anyhow::anyhow!("Invalid UTF-8 in template file: {}", e))?;
+
+        Ok(Self::process_template_content(content, context))
+    }
+
     fn create_directory_structure<'a>(
         template: &'a Dir<'a>,
         base_dir: &Path,
@@ -116,16 +225,20 @@ impl TemplateManager {
     }
 
     /// 处理模板文件 - hk.cargo.toml -> Cargo.toml
-    fn process_template_files<'a>(
+    fn render_template_files<'a>(
         template: &'a Dir<'a>,
-        base_dir: &Path,
         relative_path: &str,
-        project_name: &str,
-        device_path: &str,
+        context: &TemplateContext,
+        files: &mut Vec<(String, String)>,
     ) -> Result<()> {
         for file in template.files() {
             let file_name = file.path().file_name().unwrap().to_string_lossy();
 
+            // template.toml 只描述模板本身，不属于生成的项目
+            if relative_path.is_empty() && file_name == TEMPLATE_MANIFEST {
+                continue;
+            }
+
             let target_file_name = if file_name == "hk.cargo.toml" {
                 "Cargo.toml".to_string()
             } else {
@@ -133,19 +246,16 @@ impl TemplateManager {
             };
 
             let target_path = if relative_path.is_empty() {
-                base_dir.join(&target_file_name)
+                target_file_name
             } else {
-                base_dir.join(relative_path).join(&target_file_name)
+                format!("{}/{}", relative_path, target_file_name)
             };
 
             let content = std::str::from_utf8(file.contents())
                 .map_err(|e| anyhow::anyhow!("Invalid UTF-8 in template file: {}", e))?;
 
-            let processed_content =
-                Self::process_template_content(content, project_name, device_path);
-            std::fs::write(&target_path, processed_content)?;
-
-            println!("  📄 Created: {}", style(target_path.display()).dim());
+            let processed_content = Self::process_template_content(content, context);
+            files.push((target_path, processed_content));
         }
 
         for subdir in template.dirs() {
@@ -156,31 +266,85 @@ impl TemplateManager {
                 format!("{}/{}", relative_path, dir_name)
             };
 
-            Self::process_template_files(
-                subdir,
-                base_dir,
-                &new_relative,
-                project_name,
-                device_path,
-            )?;
+            Self::render_template_files(subdir, &new_relative, context, files)?;
         }
 
         Ok(())
     }
 
-    fn process_template_content(content: &str, project_name: &str, device_path: &str) -> String {
-        let mut processed = content.replace("{{project_name}}", project_name);
-
-        if device_path.is_empty() {
-            processed = processed.replace("{{device_path}}", "\"\"");
+    fn process_template_content(content: &str, context: &TemplateContext) -> String {
+        let flash_path = if context.flash_path.is_empty() {
+            "\"\"".to_string()
         } else {
-            let processed_device_path = device_path.replace('\\', "\\\\").replace('"', "\\\"");
-            processed = processed.replace("{{device_path}}", &processed_device_path);
+            context
+                .flash_path
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        };
+
+        let mut vars = context.variables();
+        vars.insert("flash_path", &flash_path);
+        vars.insert("device_path", &flash_path);
+
+        // 单次扫描模板文本：替换后的值不会再被当作占位符处理
+        let mut processed = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find("{{") {
+            processed.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            match after
+                .find("}}")
+                .and_then(|end| vars.get(&after[..end]).map(|value| (end, value)))
+            {
+                Some((end, value)) => {
+                    processed.push_str(value);
+                    rest = &after[end + 2..];
+                }
+                None => {
+                    processed.push_str("{{");
+                    rest = after;
+                }
+            }
         }
+        processed.push_str(rest);
 
         processed
     }
 
+    /// 计算模板内容的 SHA-256 摘要：
+    /// 对每个文件求哈希，按路径排序后再对 "哈希  路径" 列表整体求哈希
+    pub fn template_checksum(name: &str) -> Result<String> {
+        let template = Self::get_template(name)?;
+
+        let mut entries = Vec::new();
+        Self::collect_file_hashes(template, template.path(), &mut entries);
+        entries.sort();
+
+        let mut hasher = Sha256::new();
+        for (path, hash) in &entries {
+            hasher.update(format!("{}  {}\n", hash, path));
+        }
+
+        Ok(to_hex(&hasher.finalize()))
+    }
+
+    fn collect_file_hashes<'a>(dir: &'a Dir<'a>, root: &Path, entries: &mut Vec<(String, String)>) {
+        for file in dir.files() {
+            let relative = file.path().strip_prefix(root).unwrap_or(file.path());
+            // 统一使用 / 作为分隔符，保证跨平台结果一致
+            let path = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            entries.push((path, to_hex(&Sha256::digest(file.contents()))));
+        }
+
+        for subdir in dir.dirs() {
+            Self::collect_file_hashes(subdir, root, entries);
+        }
+    }
+
     pub fn install_templates_to_system() -> Result<()> {
         println!(
             "{} Templates are embedded in the binary.",
@@ -201,3 +365,7 @@ impl TemplateManager {
         Ok(())
     }
 }
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
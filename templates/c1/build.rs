@@ -17,6 +17,7 @@ fn main() {
     link_libraries(&sdk_path);
 
     println!("cargo:rerun-if-env-changed=ECOS_SDK_HOME");
+    println!("cargo:rerun-if-env-changed=ECOS_CFLAGS");
 }
 
 fn scan_sdk_directories(sdk_path: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
@@ -88,6 +89,12 @@ fn compile_c_files(c_files: &[PathBuf], include_dirs: &[PathBuf]) {
         .opt_level(2)
         .warnings(false);
 
+    if let Ok(cflags) = env::var("ECOS_CFLAGS") {
+        for flag in cflags.split_whitespace() {
+            build.flag(flag);
+        }
+    }
+
     for dir in include_dirs {
         if dir.exists() {
             build.include(dir);
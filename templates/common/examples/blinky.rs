@@ -0,0 +1,32 @@
+//! 闪烁示例：周期性切换 LED 状态并通过串口输出
+//!
+//! 构建：cargo build --example blinky
+
+#![no_std]
+#![no_main]
+
+use ecos_ssc1::ecos_main;
+
+// 忙等循环次数，按实际主频调整闪烁周期
+const DELAY_CYCLES: u32 = 5_000_000;
+
+fn delay(cycles: u32) {
+    for _ in 0..cycles {
+        core::hint::spin_loop();
+    }
+}
+
+/// 在这里接入板级 LED 驱动（GPIO 引脚见 SDK 的 board 目录）
+fn set_led(on: bool) {
+    println!("[{{project_name}}] LED {}", if on { "on" } else { "off" });
+}
+
+#[ecos_main]
+fn main() -> ! {
+    let mut on = false;
+    loop {
+        on = !on;
+        set_led(on);
+        delay(DELAY_CYCLES);
+    }
+}
@@ -0,0 +1,31 @@
+//! 串口回显示例：按行读取串口输入并原样输出
+//!
+//! 构建：cargo build --example uart_echo
+
+#![no_std]
+#![no_main]
+
+use ecos_ssc1::ecos_main;
+
+// 单行最大长度，超出部分会被截断
+const LINE_MAX: usize = 128;
+
+#[ecos_main]
+fn main() -> ! {
+    println!("[{{project_name}}] UART echo ready, type a line and press Enter");
+
+    let mut line = [0u8; LINE_MAX];
+    let mut len = 0;
+    loop {
+        let byte = ecos_ssc1::Uart::read_byte_blocking();
+        if byte == b'\n' || byte == b'\r' {
+            if let Ok(text) = core::str::from_utf8(&line[..len]) {
+                println!("echo: {}", text);
+            }
+            len = 0;
+        } else if len < LINE_MAX {
+            line[len] = byte;
+            len += 1;
+        }
+    }
+}